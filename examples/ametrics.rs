@@ -17,7 +17,7 @@ fn main() -> Result<()> {
         "req.page.4",
     ]);
 
-    // println!("{:?}", metrics.snapshot());
+    println!("{:?}", metrics.snapshot());
     println!("{}", metrics);
 
     for idx in 0..N {
@@ -0,0 +1,31 @@
+use std::time::Instant;
+
+use anyhow::Result;
+use concurrency::{multiply, multiply_blocked, Matrix};
+
+const N: usize = 256;
+const BLOCK_SIZE: usize = 32;
+
+fn main() -> Result<()> {
+    let a = Matrix::new(
+        (0..N * N).map(|x| (x % 17) as i64).collect::<Vec<_>>(),
+        N,
+        N,
+    );
+    let b = Matrix::new(
+        (0..N * N).map(|x| (x % 13) as i64).collect::<Vec<_>>(),
+        N,
+        N,
+    );
+
+    let start = Instant::now();
+    let expected = multiply(&a, &b)?;
+    println!("multiply: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let ret = multiply_blocked(&a, &b, BLOCK_SIZE)?;
+    println!("multiply_blocked({}): {:?}", BLOCK_SIZE, start.elapsed());
+
+    assert!(ret == expected);
+    Ok(())
+}
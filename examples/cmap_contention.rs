@@ -0,0 +1,61 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    thread,
+    time::Instant,
+};
+
+use anyhow::{anyhow, Result};
+use concurrency::CmapMetrics;
+
+const THREADS: usize = 8;
+const INCREMENTS: usize = 100_000;
+
+fn main() -> Result<()> {
+    // baseline: every increment goes through the same lock
+    let single = Arc::new(Mutex::new(HashMap::<String, i64>::new()));
+    let start = Instant::now();
+    let handles = (0..THREADS)
+        .map(|t| {
+            let single = single.clone();
+            thread::spawn(move || {
+                let key = format!("thread.{}", t);
+                for _ in 0..INCREMENTS {
+                    let mut map = single.lock().map_err(|e| anyhow!("{}", e))?;
+                    *map.entry(key.clone()).or_insert(0) += 1;
+                }
+                Ok::<_, anyhow::Error>(())
+            })
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle
+            .join()
+            .map_err(|e| anyhow!("Thread join error {:?}", e))??;
+    }
+    println!("single Mutex<HashMap>: {:?}", start.elapsed());
+
+    let metrics = CmapMetrics::new();
+    let start = Instant::now();
+    let handles = (0..THREADS)
+        .map(|t| {
+            let metrics = metrics.clone();
+            thread::spawn(move || {
+                let key = format!("thread.{}", t);
+                for _ in 0..INCREMENTS {
+                    metrics.inc(key.as_str())?;
+                }
+                Ok::<_, anyhow::Error>(())
+            })
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle
+            .join()
+            .map_err(|e| anyhow!("Thread join error {:?}", e))??;
+    }
+    println!("sharded CmapMetrics: {:?}", start.elapsed());
+
+    assert!(metrics.snapshot().values().all(|&v| v == INCREMENTS as i64));
+    Ok(())
+}
@@ -10,7 +10,7 @@ const M: usize = 2;
 fn main() -> Result<()> {
     let metrics = CmapMetrics::new();
 
-    // println!("{:?}", metrics.snapshot());
+    println!("{:?}", metrics.snapshot());
     println!("{}", metrics);
 
     for idx in 0..N {
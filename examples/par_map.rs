@@ -0,0 +1,25 @@
+use std::{thread, time::Duration, time::Instant};
+
+use concurrency::Matrix;
+
+const N: usize = 16;
+
+// stands in for an expensive per-element function
+fn slow(x: &i64) -> i64 {
+    thread::sleep(Duration::from_millis(1));
+    x * 2
+}
+
+fn main() {
+    let m = Matrix::new((0..(N * N) as i64).collect::<Vec<_>>(), N, N);
+
+    let start = Instant::now();
+    let expected = m.map(slow);
+    println!("map: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let ret = m.par_map(slow);
+    println!("par_map: {:?}", start.elapsed());
+
+    assert!(ret == expected);
+}
@@ -0,0 +1,29 @@
+use std::time::Instant;
+
+use anyhow::Result;
+use concurrency::{dot_product, Vector};
+
+const N: usize = 4096;
+const ROUNDS: usize = 10_000;
+
+fn main() -> Result<()> {
+    let a = (0..N).map(|x| x as f64 * 0.5).collect::<Vec<_>>();
+    let b = (0..N).map(|x| x as f64 * 0.25).collect::<Vec<_>>();
+
+    let start = Instant::now();
+    let mut scalar = 0.0;
+    for _ in 0..ROUNDS {
+        scalar = a.iter().zip(&b).map(|(x, y)| x * y).sum::<f64>();
+    }
+    println!("scalar: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let mut simd = 0.0;
+    for _ in 0..ROUNDS {
+        simd = dot_product(Vector::new(a.as_slice()), Vector::new(b.as_slice()))?;
+    }
+    println!("dot_product (simd): {:?}", start.elapsed());
+
+    println!("scalar = {}, simd = {}", scalar, simd);
+    Ok(())
+}
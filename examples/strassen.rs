@@ -0,0 +1,30 @@
+use std::time::Instant;
+
+use anyhow::Result;
+use concurrency::{multiply, multiply_strassen, Matrix};
+
+const N: usize = 256;
+
+fn main() -> Result<()> {
+    let a = Matrix::new(
+        (0..N * N).map(|x| (x % 17) as i64).collect::<Vec<_>>(),
+        N,
+        N,
+    );
+    let b = Matrix::new(
+        (0..N * N).map(|x| (x % 13) as i64).collect::<Vec<_>>(),
+        N,
+        N,
+    );
+
+    let start = Instant::now();
+    let expected = multiply(&a, &b)?;
+    println!("multiply: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let ret = multiply_strassen(&a, &b)?;
+    println!("multiply_strassen: {:?}", start.elapsed());
+
+    assert!(ret == expected);
+    Ok(())
+}
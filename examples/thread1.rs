@@ -1,6 +1,7 @@
-use std::{sync::mpsc, thread, time::Duration};
+use std::{iter, thread, time::Duration};
 
 use anyhow::{anyhow, Result};
+use concurrency::spawn_producers;
 
 #[allow(dead_code)]
 #[derive(Debug)]
@@ -13,44 +14,39 @@ const NUM_PRODUCERS: usize = 4;
 
 fn main() -> Result<()> {
     // print!("Hello World!");
-    let (tx, rx) = mpsc::channel();
-    for i in 0..NUM_PRODUCERS {
-        let tx = tx.clone();
-        thread::spawn(move || producer(i, tx));
-    }
-    drop(tx);
-
-    let consumer = thread::spawn(move || {
+    let consumer = spawn_producers(NUM_PRODUCERS, producer, |rx| {
         for msg in rx {
             println!("consume {:?}", msg);
         }
     });
 
-    let _ = consumer
+    consumer
         .join()
-        .map_err(|e| anyhow!("Thread join error {:?}", e));
+        .map_err(|e| anyhow!("Thread join error {:?}", e))??;
 
     println!("Bye");
     Ok(())
 }
 
-fn producer(idx: usize, tx: mpsc::Sender<Msg>) -> Result<()> {
-    loop {
+fn producer(idx: usize) -> impl Iterator<Item = Msg> {
+    let mut done = false;
+    iter::from_fn(move || {
+        if done {
+            return None;
+        }
         let value = rand::random::<usize>();
-        tx.send(Msg { idx, value })?;
         let sleep_time = rand::random::<u8>() as u64 * 10;
         thread::sleep(Duration::from_millis(sleep_time));
 
         let random_exit = rand::random::<u8>();
-        if random_exit % 10 == 0 {
+        if random_exit.is_multiple_of(10) {
             println!("exit {}", idx);
-            break;
+            done = true;
         }
-    }
-    Ok(())
+        Some(Msg::new(idx, value))
+    })
 }
 
-#[allow(dead_code)]
 impl Msg {
     fn new(idx: usize, value: usize) -> Self {
         Self { idx, value }
@@ -0,0 +1,72 @@
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::metrics::MetricKind;
+
+pub type Result<T, E = ConcurrencyError> = std::result::Result<T, E>;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ConcurrencyError {
+    #[error("dimension mismatch: expected {expected:?}, got {got:?}")]
+    DimensionMismatch {
+        expected: (usize, usize),
+        got: (usize, usize),
+    },
+    #[error("length mismatch: expected {expected}, got {got}")]
+    LengthMismatch { expected: usize, got: usize },
+    #[error("matrix is not square: {row}x{col}")]
+    NotSquare { row: usize, col: usize },
+    #[error("index ({row}, {col}) out of range for {rows}x{cols} matrix")]
+    IndexOutOfRange {
+        row: usize,
+        col: usize,
+        rows: usize,
+        cols: usize,
+    },
+    #[error("matrix is singular")]
+    Singular,
+    #[error("empty input")]
+    EmptyInput,
+    #[error("zero vector")]
+    ZeroVector,
+    #[error("arithmetic overflow")]
+    Overflow,
+    #[error("parse error: {0}")]
+    Parse(String),
+    #[error("invalid argument: {0}")]
+    InvalidArgument(String),
+    #[error("key: {0} not found")]
+    KeyNotFound(String),
+    #[error("key: {key} is a {kind}")]
+    KindMismatch { key: String, kind: MetricKind },
+    #[error("timed out after {0:?}")]
+    Timeout(Duration),
+    #[error("operation cancelled")]
+    Cancelled,
+    #[error("worker error: {0}")]
+    Worker(String),
+    #[error("{count} producer(s) failed: {0:?}", count = .0.len())]
+    Producers(Vec<ConcurrencyError>),
+    #[error("consumer failed: {0}")]
+    Consumer(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fails() -> Result<()> {
+        Err(ConcurrencyError::KeyNotFound("requests".to_string()))
+    }
+
+    #[test]
+    fn test_converts_into_anyhow() {
+        let ret: anyhow::Result<()> = (|| Ok(fails()?))();
+        let err = ret.unwrap_err();
+        assert_eq!(err.to_string(), "key: requests not found");
+        assert_eq!(
+            err.downcast_ref::<ConcurrencyError>(),
+            Some(&ConcurrencyError::KeyNotFound("requests".to_string()))
+        );
+    }
+}
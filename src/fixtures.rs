@@ -0,0 +1,99 @@
+// Element types with misbehaving arithmetic, shared by the unit tests.
+
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::Duration,
+};
+
+// Defines a numeric newtype over `$inner` with the usual Add, AddAssign and
+// Display; only the product is special, computed by `$mul` from the two
+// wrapped values bound to `$a` and `$b`.
+macro_rules! fixture {
+    ($(#[$attr:meta])* $name:ident($inner:ty), |$a:ident, $b:ident| $mul:expr) => {
+        $(#[$attr])*
+        #[derive(Debug, Default, PartialEq)]
+        pub(crate) struct $name(pub(crate) $inner);
+
+        impl std::ops::Add for $name {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                $name(self.0 + rhs.0)
+            }
+        }
+
+        impl std::ops::AddAssign for $name {
+            fn add_assign(&mut self, rhs: Self) {
+                self.0 += rhs.0;
+            }
+        }
+
+        impl std::ops::Mul for $name {
+            type Output = Self;
+
+            fn mul(self, rhs: Self) -> Self {
+                let ($a, $b) = (self.0, rhs.0);
+                $name($mul)
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+    };
+}
+
+pub(crate) use fixture;
+
+// multiplying two Bombs of 13 panics, standing in for an overflow in debug builds
+fixture!(
+    #[derive(Clone, Copy)]
+    Bomb(i32),
+    |a, b| {
+        if a == 13 && b == 13 {
+            panic!("boom");
+        }
+        a * b
+    }
+);
+
+// Clone but not Copy
+fixture!(
+    #[derive(Clone)]
+    Big(i64),
+    |a, b| a * b
+);
+
+// every product takes a while, standing in for a stuck worker; SLOW_RUNNING
+// counts the products still in progress
+pub(crate) static SLOW_RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+fixture!(
+    #[derive(Clone, Copy)]
+    Slow(i32),
+    |a, b| {
+        SLOW_RUNNING.fetch_add(1, Ordering::Relaxed);
+        thread::sleep(Duration::from_millis(100));
+        SLOW_RUNNING.fetch_sub(1, Ordering::Relaxed);
+        a * b
+    }
+);
+
+// every product takes a millisecond and is counted in TICKS; two Ticks of 13 panic
+pub(crate) static TICKS: AtomicUsize = AtomicUsize::new(0);
+
+fixture!(
+    #[derive(Clone, Copy)]
+    Tick(i32),
+    |a, b| {
+        if a == 13 && b == 13 {
+            panic!("boom");
+        }
+        TICKS.fetch_add(1, Ordering::Relaxed);
+        thread::sleep(Duration::from_millis(1));
+        a * b
+    }
+);
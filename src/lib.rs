@@ -1,7 +1,31 @@
+pub mod error;
 pub mod matrix;
 pub mod metrics;
+pub mod pipeline;
+pub mod pool;
 pub mod vector;
 
-pub use matrix::{multiply, Matrix};
-pub use metrics::{amap::AmapMetrics, cmap::CmapMetrics};
-pub use vector::{dot_product, Vector};
+#[cfg(test)]
+mod fixtures;
+
+pub use error::ConcurrencyError;
+#[cfg(feature = "tokio")]
+pub use matrix::multiply_async;
+pub use matrix::{
+    add, assert_matrix_eq, hadamard, hstack, kron, multiply, multiply_as, multiply_blocked,
+    multiply_bounded, multiply_cancellable, multiply_checked, multiply_into, multiply_owned,
+    multiply_ref, multiply_strassen, multiply_with_stats, multiply_with_threads,
+    multiply_with_timeout, solve, subtract, vstack, Matrix, MatrixMultiplier, MultiplyStats,
+    MultiplySync,
+};
+pub use metrics::{
+    amap::AmapMetrics, cmap::CmapMetrics, histogram::Histogram, registry::MetricsRegistry,
+    sharded::ShardedMetrics, MetricKind, Metrics,
+};
+pub use pipeline::{run_pipeline, spawn_producers};
+pub use pool::ThreadPool;
+pub use vector::{
+    cosine_similarity, cross_product, dot_product, dot_product_acc, dot_product_checked,
+    dot_product_parallel, dot_product_saturating, dot_slice, outer_product, weighted_dot_product,
+    CheckedNum, SaturatingNum, Vector,
+};
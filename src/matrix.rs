@@ -1,18 +1,28 @@
-use anyhow::{anyhow, Result};
 use core::fmt;
+use num_traits::{Float, One};
 use std::{
     fmt::{Debug, Display},
-    ops::{Add, AddAssign, Mul},
-    sync::mpsc,
-    thread,
+    ops::{Add, AddAssign, Mul, Range, Sub},
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
-use crate::{dot_product, Vector};
+use crate::{
+    dot_product,
+    error::{ConcurrencyError, Result},
+    vector::{dot_product_checked, dot_slice, CheckedNum},
+    Vector,
+};
 
 const NUM_THREADS: usize = 4;
+const STRASSEN_THRESHOLD: usize = 32;
 
 #[allow(dead_code)]
-#[derive(PartialEq)]
 pub struct Matrix<T: Debug> {
     data: Vec<T>,
     row: usize,
@@ -32,7 +42,7 @@ pub struct MsgOutput<T> {
 
 pub struct Msg<T> {
     input: MsgInput<T>,
-    sender: oneshot::Sender<MsgOutput<T>>,
+    sender: oneshot::Sender<Result<MsgOutput<T>>>,
 }
 
 #[allow(dead_code)]
@@ -41,14 +51,832 @@ where
     T: Debug,
 {
     pub fn new(data: impl Into<Vec<T>>, row: usize, col: usize) -> Self {
-        Self {
-            data: data.into(),
-            row,
-            col,
+        Self::try_new(data, row, col).expect("Matrix new error")
+    }
+
+    pub fn try_new(data: impl Into<Vec<T>>, row: usize, col: usize) -> Result<Self> {
+        let data = data.into();
+        if data.len() != row * col {
+            return Err(ConcurrencyError::LengthMismatch {
+                expected: row * col,
+                got: data.len(),
+            });
+        }
+        Ok(Self { data, row, col })
+    }
+
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Self> {
+        let row = rows.len();
+        let col = rows.first().map_or(0, |r| r.len());
+        if row == 0 || col == 0 {
+            return Err(ConcurrencyError::EmptyInput);
+        }
+        if let Some(i) = rows.iter().position(|r| r.len() != col) {
+            return Err(ConcurrencyError::LengthMismatch {
+                expected: col,
+                got: rows[i].len(),
+            });
+        }
+
+        let data = rows.into_iter().flatten().collect::<Vec<_>>();
+        Ok(Self::new(data, row, col))
+    }
+
+    pub fn zeros(row: usize, col: usize) -> Self
+    where
+        T: Default + Clone,
+    {
+        Self::filled(row, col, T::default())
+    }
+
+    pub fn filled(row: usize, col: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self::new(vec![value; row * col], row, col)
+    }
+
+    pub fn identity(n: usize) -> Self
+    where
+        T: Default + Clone + One,
+    {
+        let mut m = Self::zeros(n, n);
+        for i in 0..n {
+            m.data[i * n + i] = T::one();
+        }
+        m
+    }
+
+    pub fn transpose(&self) -> Matrix<T>
+    where
+        T: Copy,
+    {
+        let mut data = Vec::with_capacity(self.data.len());
+        for j in 0..self.col {
+            for i in 0..self.row {
+                data.push(self.data[i * self.col + j]);
+            }
+        }
+        Matrix::new(data, self.col, self.row)
+    }
+
+    /// Multiplies every element by `factor`. `m * factor` does the same, while
+    /// `m * other` with another matrix is the matrix product.
+    pub fn scale(&self, factor: T) -> Matrix<T>
+    where
+        T: Copy + Mul<Output = T>,
+    {
+        let data = self.data.iter().map(|&x| x * factor).collect::<Vec<_>>();
+        Matrix::new(data, self.row, self.col)
+    }
+
+    pub fn mul_vector(&self, v: &Vector<T>) -> Result<Vector<T>>
+    where
+        T: Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
+    {
+        if self.col != v.len() {
+            return Err(ConcurrencyError::LengthMismatch {
+                expected: self.col,
+                got: v.len(),
+            });
+        }
+
+        let mut data = Vec::with_capacity(self.row);
+        for i in 0..self.row {
+            let row = Vector::new(&self.data[i * self.col..(i + 1) * self.col]);
+            data.push(dot_product(row, Vector::new(v.as_slice()))?);
+        }
+        Ok(Vector::new(data))
+    }
+
+    pub fn power(&self, n: u32) -> Result<Matrix<T>>
+    where
+        T: Copy
+            + Default
+            + One
+            + Add<Output = T>
+            + AddAssign
+            + Mul<Output = T>
+            + Send
+            + MultiplySync
+            + 'static,
+    {
+        if self.row != self.col {
+            return Err(ConcurrencyError::NotSquare {
+                row: self.row,
+                col: self.col,
+            });
+        }
+
+        let mut result = Matrix::identity(self.row);
+        let mut base = self.clone();
+        let mut n = n;
+        while n > 0 {
+            if n & 1 == 1 {
+                result = multiply(&result, &base)?;
+            }
+            n >>= 1;
+            if n > 0 {
+                base = multiply(&base, &base)?;
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.row).map(move |i| &self.data[i * self.col..(i + 1) * self.col])
+    }
+
+    /// The inverse of [`Matrix::from_rows`].
+    pub fn to_vec_of_rows(&self) -> Vec<Vec<T>>
+    where
+        T: Clone,
+    {
+        self.rows().map(|r| r.to_vec()).collect()
+    }
+
+    /// Renders one line per row with every column right-aligned to its widest
+    /// cell, formatting each element with `precision` decimals.
+    pub fn format_aligned(&self, precision: usize) -> String
+    where
+        T: Display,
+    {
+        let cells = self
+            .data
+            .iter()
+            .map(|v| format!("{:.*}", precision, v))
+            .collect::<Vec<_>>();
+        let widths = (0..self.col)
+            .map(|j| {
+                (0..self.row)
+                    .map(|i| cells[i * self.col + j].len())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+
+        (0..self.row)
+            .map(|i| {
+                (0..self.col)
+                    .map(|j| format!("{:>w$}", cells[i * self.col + j], w = widths[j]))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Comma-separated rows joined by newlines, the format [`Matrix::from_csv`] reads.
+    pub fn to_csv(&self) -> String
+    where
+        T: Display,
+    {
+        self.rows()
+            .map(|r| {
+                r.iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Reverses every row.
+    pub fn flip_horizontal(&self) -> Matrix<T>
+    where
+        T: Clone,
+    {
+        let data = self
+            .rows()
+            .flat_map(|r| r.iter().rev().cloned())
+            .collect::<Vec<_>>();
+        Matrix::new(data, self.row, self.col)
+    }
+
+    /// Reverses the order of the rows.
+    pub fn flip_vertical(&self) -> Matrix<T>
+    where
+        T: Clone,
+    {
+        let data = self
+            .data
+            .chunks(self.col.max(1))
+            .rev()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+        Matrix::new(data, self.row, self.col)
+    }
+
+    /// Rotates clockwise, so a `row x col` matrix becomes `col x row`.
+    pub fn rotate90(&self) -> Matrix<T>
+    where
+        T: Clone,
+    {
+        let mut data = Vec::with_capacity(self.data.len());
+        for i in 0..self.col {
+            for j in 0..self.row {
+                data.push(self.data[(self.row - 1 - j) * self.col + i].clone());
+            }
+        }
+        Matrix::new(data, self.col, self.row)
+    }
+
+    pub fn col(&self, j: usize) -> Option<Vec<T>>
+    where
+        T: Clone,
+    {
+        if j >= self.col {
+            return None;
+        }
+        Some(self.data[j..].iter().step_by(self.col).cloned().collect())
+    }
+
+    pub fn approx_eq(&self, other: &Matrix<T>, epsilon: T) -> bool
+    where
+        T: Float,
+    {
+        self.row == other.row
+            && self.col == other.col
+            && self
+                .data
+                .iter()
+                .zip(other.data.iter())
+                .all(|(&x, &y)| (x - y).abs() < epsilon)
+    }
+
+    /// Like [`Matrix::approx_eq`], but NaNs in the same position compare equal.
+    pub fn approx_eq_nan_equal(&self, other: &Matrix<T>, epsilon: T) -> bool
+    where
+        T: Float,
+    {
+        self.row == other.row
+            && self.col == other.col
+            && self
+                .data
+                .iter()
+                .zip(other.data.iter())
+                .all(|(&x, &y)| (x.is_nan() && y.is_nan()) || x == y || (x - y).abs() < epsilon)
+    }
+
+    pub fn map<U, F>(&self, f: F) -> Matrix<U>
+    where
+        F: Fn(&T) -> U,
+        U: Debug,
+    {
+        Matrix {
+            data: self.data.iter().map(f).collect(),
+            row: self.row,
+            col: self.col,
+        }
+    }
+
+    /// Like [`Matrix::map`], but splits the elements across one scoped thread per
+    /// available core. Only worth it when `f` is expensive; a panic in `f` is
+    /// propagated to the caller.
+    pub fn par_map<U, F>(&self, f: F) -> Matrix<U>
+    where
+        T: Sync,
+        F: Fn(&T) -> U + Sync,
+        U: Debug + Send,
+    {
+        let num_threads = default_num_threads();
+        let chunk_size = self.data.len().div_ceil(num_threads).max(1);
+
+        let f = &f;
+        let data = thread::scope(|s| {
+            let handles = self
+                .data
+                .chunks(chunk_size)
+                .map(|chunk| s.spawn(move || chunk.iter().map(f).collect::<Vec<_>>()))
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+                .collect()
+        });
+
+        Matrix {
+            data,
+            row: self.row,
+            col: self.col,
+        }
+    }
+
+    pub fn gt(&self, scalar: T) -> Matrix<bool>
+    where
+        T: PartialOrd + Copy,
+    {
+        self.map(|&v| v > scalar)
+    }
+
+    pub fn lt(&self, scalar: T) -> Matrix<bool>
+    where
+        T: PartialOrd + Copy,
+    {
+        self.map(|&v| v < scalar)
+    }
+
+    pub fn ge(&self, scalar: T) -> Matrix<bool>
+    where
+        T: PartialOrd + Copy,
+    {
+        self.map(|&v| v >= scalar)
+    }
+
+    pub fn le(&self, scalar: T) -> Matrix<bool>
+    where
+        T: PartialOrd + Copy,
+    {
+        self.map(|&v| v <= scalar)
+    }
+
+    /// Picks `a`'s element wherever `mask` is true and `b`'s everywhere else.
+    pub fn select(mask: &Matrix<bool>, a: &Matrix<T>, b: &Matrix<T>) -> Result<Matrix<T>>
+    where
+        T: Copy,
+    {
+        for m in [(a.row, a.col), (b.row, b.col)] {
+            if m != (mask.row, mask.col) {
+                return Err(ConcurrencyError::DimensionMismatch {
+                    expected: (mask.row, mask.col),
+                    got: m,
+                });
+            }
+        }
+
+        let data = mask
+            .data
+            .iter()
+            .zip(a.data.iter().zip(b.data.iter()))
+            .map(|(&m, (&x, &y))| if m { x } else { y });
+        Ok(Matrix::new(data.collect::<Vec<_>>(), a.row, a.col))
+    }
+
+    /// Unlike [`Matrix::reshape`], element `(i, j)` stays at `(i, j)`: growing
+    /// fills the new cells with `fill` and shrinking drops the cut-off ones.
+    pub fn resize(&self, new_row: usize, new_col: usize, fill: T) -> Matrix<T>
+    where
+        T: Copy,
+    {
+        let mut data = vec![fill; new_row * new_col];
+        let col = self.col.min(new_col);
+        for i in 0..self.row.min(new_row) {
+            data[i * new_col..i * new_col + col]
+                .copy_from_slice(&self.data[i * self.col..i * self.col + col]);
+        }
+        Matrix::new(data, new_row, new_col)
+    }
+
+    pub fn reshape(&self, row: usize, col: usize) -> Result<Matrix<T>>
+    where
+        T: Clone,
+    {
+        Matrix::try_new(self.data.clone(), row, col)
+    }
+
+    pub fn submatrix(&self, row_range: Range<usize>, col_range: Range<usize>) -> Result<Matrix<T>>
+    where
+        T: Copy,
+    {
+        if row_range.start > row_range.end
+            || col_range.start > col_range.end
+            || row_range.end > self.row
+            || col_range.end > self.col
+        {
+            return Err(ConcurrencyError::IndexOutOfRange {
+                row: row_range.end,
+                col: col_range.end,
+                rows: self.row,
+                cols: self.col,
+            });
+        }
+
+        let (row, col) = (row_range.len(), col_range.len());
+        let mut data = Vec::with_capacity(row * col);
+        for i in row_range {
+            let start = i * self.col;
+            data.extend_from_slice(&self.data[start + col_range.start..start + col_range.end]);
+        }
+        Ok(Matrix::new(data, row, col))
+    }
+
+    /// Position of the largest element; on ties the first one in row-major order wins.
+    pub fn argmax(&self) -> Option<(usize, usize)>
+    where
+        T: PartialOrd + Copy,
+    {
+        let (idx, _) = self
+            .data
+            .iter()
+            .enumerate()
+            .reduce(|max, x| if x.1 > max.1 { x } else { max })?;
+        Some((idx / self.col, idx % self.col))
+    }
+
+    pub fn has_nan(&self) -> bool
+    where
+        T: Float,
+    {
+        self.data.iter().any(|v| v.is_nan())
+    }
+
+    /// The row-major backing data.
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    /// `(row, col)`.
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.row, self.col)
+    }
+
+    pub fn is_square(&self) -> bool {
+        self.row == self.col
+    }
+
+    pub fn is_symmetric(&self) -> bool
+    where
+        T: PartialEq,
+    {
+        self.is_square()
+            && (0..self.row).all(|i| {
+                (i + 1..self.col)
+                    .all(|j| self.data[i * self.col + j] == self.data[j * self.col + i])
+            })
+    }
+
+    pub fn diagonal(&self) -> Result<Vector<T>>
+    where
+        T: Copy,
+    {
+        if self.row != self.col {
+            return Err(ConcurrencyError::NotSquare {
+                row: self.row,
+                col: self.col,
+            });
+        }
+
+        Ok((0..self.row).map(|i| self.data[i * self.col + i]).collect())
+    }
+
+    pub fn trace(&self) -> Result<T>
+    where
+        T: Copy + Default + AddAssign,
+    {
+        if self.row != self.col {
+            return Err(ConcurrencyError::NotSquare {
+                row: self.row,
+                col: self.col,
+            });
+        }
+
+        let mut sum = T::default();
+        for i in 0..self.row {
+            sum += self.data[i * self.col + i];
+        }
+        Ok(sum)
+    }
+
+    pub fn determinant(&self) -> Result<T>
+    where
+        T: Float,
+    {
+        if self.row != self.col {
+            return Err(ConcurrencyError::NotSquare {
+                row: self.row,
+                col: self.col,
+            });
+        }
+
+        // gaussian elimination with partial pivoting, the determinant is the
+        // product of the pivots with a sign flip per row swap
+        let n = self.row;
+        let mut m = self.data.clone();
+        let mut det = T::one();
+        for k in 0..n {
+            let pivot = (k..n)
+                .max_by(|&i, &j| {
+                    m[i * n + k]
+                        .abs()
+                        .partial_cmp(&m[j * n + k].abs())
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .unwrap_or(k);
+            if m[pivot * n + k].is_zero() {
+                return Ok(T::zero());
+            }
+            if pivot != k {
+                for j in 0..n {
+                    m.swap(k * n + j, pivot * n + j);
+                }
+                det = -det;
+            }
+
+            let p = m[k * n + k];
+            det = det * p;
+            for i in k + 1..n {
+                let factor = m[i * n + k] / p;
+                for j in k..n {
+                    m[i * n + j] = m[i * n + j] - factor * m[k * n + j];
+                }
+            }
+        }
+        Ok(det)
+    }
+
+    pub fn inverse(&self) -> Result<Matrix<T>>
+    where
+        T: Float,
+    {
+        if self.row != self.col {
+            return Err(ConcurrencyError::NotSquare {
+                row: self.row,
+                col: self.col,
+            });
+        }
+
+        // gauss-jordan on [self | I], once the left half is I the right half is the inverse
+        let n = self.row;
+        let w = 2 * n;
+        let mut m = vec![T::zero(); n * w];
+        for i in 0..n {
+            m[i * w..i * w + n].copy_from_slice(&self.data[i * n..(i + 1) * n]);
+            m[i * w + n + i] = T::one();
+        }
+
+        let scale = self.data.iter().fold(T::zero(), |acc, x| acc.max(x.abs()));
+        let tolerance = T::epsilon() * scale * T::from(n).unwrap_or_else(T::one);
+        for k in 0..n {
+            let pivot = (k..n)
+                .max_by(|&i, &j| {
+                    m[i * w + k]
+                        .abs()
+                        .partial_cmp(&m[j * w + k].abs())
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .unwrap_or(k);
+            if m[pivot * w + k].abs() <= tolerance {
+                return Err(ConcurrencyError::Singular);
+            }
+            if pivot != k {
+                for j in 0..w {
+                    m.swap(k * w + j, pivot * w + j);
+                }
+            }
+
+            let p = m[k * w + k];
+            for j in 0..w {
+                m[k * w + j] = m[k * w + j] / p;
+            }
+            for i in (0..n).filter(|&i| i != k) {
+                let factor = m[i * w + k];
+                if factor.is_zero() {
+                    continue;
+                }
+                for j in 0..w {
+                    m[i * w + j] = m[i * w + j] - factor * m[k * w + j];
+                }
+            }
+        }
+
+        let data = m
+            .chunks(w)
+            .flat_map(|row| row[n..].iter().copied())
+            .collect::<Vec<_>>();
+        Ok(Matrix::new(data, n, n))
+    }
+
+    /// LU decomposition with partial pivoting: returns `(L, U, perm)` with `L`
+    /// unit lower-triangular, `U` upper-triangular and `P * A = L * U`, where row
+    /// `i` of `P * A` is row `perm[i]` of `A`.
+    pub fn lu(&self) -> Result<(Matrix<T>, Matrix<T>, Vec<usize>)>
+    where
+        T: Float,
+    {
+        if self.row != self.col {
+            return Err(ConcurrencyError::NotSquare {
+                row: self.row,
+                col: self.col,
+            });
+        }
+
+        // doolittle elimination in place: U ends up on and above the diagonal,
+        // the multipliers of L below it
+        let n = self.row;
+        let mut m = self.data.clone();
+        let mut perm = (0..n).collect::<Vec<_>>();
+        let scale = self.data.iter().fold(T::zero(), |acc, x| acc.max(x.abs()));
+        let tolerance = T::epsilon() * scale * T::from(n).unwrap_or_else(T::one);
+        for k in 0..n {
+            let pivot = (k..n)
+                .max_by(|&i, &j| {
+                    m[i * n + k]
+                        .abs()
+                        .partial_cmp(&m[j * n + k].abs())
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .unwrap_or(k);
+            if m[pivot * n + k].abs() <= tolerance {
+                return Err(ConcurrencyError::Singular);
+            }
+            if pivot != k {
+                for j in 0..n {
+                    m.swap(k * n + j, pivot * n + j);
+                }
+                perm.swap(k, pivot);
+            }
+
+            let p = m[k * n + k];
+            for i in k + 1..n {
+                let factor = m[i * n + k] / p;
+                m[i * n + k] = factor;
+                for j in k + 1..n {
+                    m[i * n + j] = m[i * n + j] - factor * m[k * n + j];
+                }
+            }
+        }
+
+        let (mut l, mut u) = (vec![T::zero(); n * n], vec![T::zero(); n * n]);
+        for i in 0..n {
+            l[i * n + i] = T::one();
+            for j in 0..n {
+                if j < i {
+                    l[i * n + j] = m[i * n + j];
+                } else {
+                    u[i * n + j] = m[i * n + j];
+                }
+            }
+        }
+        Ok((Matrix::new(l, n, n), Matrix::new(u, n, n), perm))
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        if row >= self.row || col >= self.col {
+            return None;
+        }
+        self.data.get(row * self.col + col)
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, value: T) -> Result<()> {
+        if row >= self.row || col >= self.col {
+            return Err(ConcurrencyError::IndexOutOfRange {
+                row,
+                col,
+                rows: self.row,
+                cols: self.col,
+            });
+        }
+        self.data[row * self.col + col] = value;
+        Ok(())
+    }
+
+    pub fn apply_row(&mut self, i: usize, f: impl FnMut(&mut T)) -> Result<()> {
+        if i >= self.row {
+            return Err(ConcurrencyError::IndexOutOfRange {
+                row: i,
+                col: 0,
+                rows: self.row,
+                cols: self.col,
+            });
+        }
+        self.data[i * self.col..(i + 1) * self.col]
+            .iter_mut()
+            .for_each(f);
+        Ok(())
+    }
+
+    pub fn apply_col(&mut self, j: usize, f: impl FnMut(&mut T)) -> Result<()> {
+        if j >= self.col {
+            return Err(ConcurrencyError::IndexOutOfRange {
+                row: 0,
+                col: j,
+                rows: self.row,
+                cols: self.col,
+            });
+        }
+        self.data[j..].iter_mut().step_by(self.col).for_each(f);
+        Ok(())
+    }
+
+    pub fn swap_rows(&mut self, a: usize, b: usize) -> Result<()> {
+        if a.max(b) >= self.row {
+            return Err(ConcurrencyError::IndexOutOfRange {
+                row: a.max(b),
+                col: 0,
+                rows: self.row,
+                cols: self.col,
+            });
+        }
+        if a == b {
+            return Ok(());
+        }
+
+        let (lo, hi) = (a.min(b), a.max(b));
+        let (head, tail) = self.data.split_at_mut(hi * self.col);
+        head[lo * self.col..(lo + 1) * self.col].swap_with_slice(&mut tail[..self.col]);
+        Ok(())
+    }
+
+    pub fn swap_cols(&mut self, a: usize, b: usize) -> Result<()> {
+        if a.max(b) >= self.col {
+            return Err(ConcurrencyError::IndexOutOfRange {
+                row: 0,
+                col: a.max(b),
+                rows: self.row,
+                cols: self.col,
+            });
+        }
+
+        for i in 0..self.row {
+            self.data.swap(i * self.col + a, i * self.col + b);
+        }
+        Ok(())
+    }
+}
+
+impl Matrix<f64> {
+    /// Parses newline-separated rows of comma-separated numbers. Cells are
+    /// trimmed and blank lines are skipped; every row must have the same length.
+    /// Parse errors name the 1-based line of `text` they came from.
+    pub fn from_csv(text: &str) -> Result<Self> {
+        let rows = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                line.split(',')
+                    .map(|cell| {
+                        let cell = cell.trim();
+                        cell.parse::<f64>().map_err(|e| {
+                            ConcurrencyError::Parse(format!("line {}: {:?}: {}", i + 1, cell, e))
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::from_rows(rows)
+    }
+}
+
+impl TryFrom<&str> for Matrix<f64> {
+    type Error = ConcurrencyError;
+
+    fn try_from(text: &str) -> Result<Self> {
+        Self::from_csv(text)
+    }
+}
+
+impl<T> Clone for Matrix<T>
+where
+    T: Clone + Debug,
+{
+    fn clone(&self) -> Self {
+        Matrix {
+            data: self.data.clone(),
+            row: self.row,
+            col: self.col,
         }
     }
 }
 
+/// Two matrices are equal only if they have the same `row`, the same `col` and
+/// the same elements in row-major order, so a 1x4 and a 4x1 holding the same
+/// data are different matrices.
+impl<T> PartialEq for Matrix<T>
+where
+    T: Debug + PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.row == other.row && self.col == other.col && self.data == other.data
+    }
+}
+
+/// Like `assert_eq!`, but the panic message says whether the rows, the columns
+/// or a specific element differ.
+#[track_caller]
+pub fn assert_matrix_eq<T>(left: &Matrix<T>, right: &Matrix<T>)
+where
+    T: Debug + PartialEq,
+{
+    if left.row != right.row || left.col != right.col {
+        panic!(
+            "matrices differ in shape: left is {}x{}, right is {}x{}",
+            left.row, left.col, right.row, right.col
+        );
+    }
+    if let Some(idx) = (0..left.data.len()).find(|&i| left.data[i] != right.data[i]) {
+        panic!(
+            "matrices differ at ({}, {}): left is {:?}, right is {:?}",
+            idx / left.col,
+            idx % left.col,
+            left.data[idx],
+            right.data[idx]
+        );
+    }
+}
+
 impl<T> Display for Matrix<T>
 where
     T: Debug,
@@ -80,68 +908,342 @@ where
     }
 }
 
+/// Extra element bound of [`multiply`] and everything built on it: `Sync` with
+/// the `rayon` feature, whose threads read the inputs in place, and nothing
+/// at all without it.
+#[cfg(feature = "rayon")]
+pub trait MultiplySync: Sync {}
+
+#[cfg(feature = "rayon")]
+impl<T: Sync> MultiplySync for T {}
+
+/// Extra element bound of [`multiply`] and everything built on it: `Sync` with
+/// the `rayon` feature, whose threads read the inputs in place, and nothing
+/// at all without it.
+#[cfg(not(feature = "rayon"))]
+pub trait MultiplySync {}
+
+#[cfg(not(feature = "rayon"))]
+impl<T> MultiplySync for T {}
+
 #[allow(dead_code)]
+/// With the `rayon` feature the cells are computed on rayon's global pool,
+/// otherwise on one std thread per available core.
 pub fn multiply<T>(a: &Matrix<T>, b: &Matrix<T>) -> Result<Matrix<T>>
 where
-    T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
+    T: Debug
+        + Copy
+        + Default
+        + Add<Output = T>
+        + AddAssign
+        + Mul<Output = T>
+        + Send
+        + MultiplySync
+        + 'static,
 {
-    if a.col != b.row {
-        return Err(anyhow!("Matrix multiply error : a.row != b.col"));
-        // panic!("Matrix multiply error : a.row != b.col");
-    }
-
-    let senders = (0..NUM_THREADS)
-        .map(|_| {
-            let (tx, rx) = mpsc::channel::<Msg<T>>();
-            thread::spawn(move || {
-                for msg in rx {
-                    let value = dot_product(msg.input.row, msg.input.col)?;
-                    if let Err(e) = msg.sender.send(MsgOutput {
-                        idx: msg.input.idx,
-                        value,
-                    }) {
-                        eprintln!("{}", e);
-                    };
-                }
-                Ok::<_, anyhow::Error>(())
-            });
-            tx
-        })
-        .collect::<Vec<_>>();
+    #[cfg(feature = "rayon")]
+    {
+        multiply_rayon(a, b)
+    }
 
-    let matrix_len = a.row * b.col;
-    let mut data = vec![T::default(); matrix_len];
+    #[cfg(not(feature = "rayon"))]
+    {
+        let num_threads = default_num_threads();
+        multiply_with_threads(a, b, num_threads)
+    }
+}
 
-    let mut receivers = Vec::with_capacity(matrix_len);
+#[cfg(feature = "rayon")]
+fn multiply_rayon<T>(a: &Matrix<T>, b: &Matrix<T>) -> Result<Matrix<T>>
+where
+    T: Debug
+        + Copy
+        + Default
+        + Add<Output = T>
+        + AddAssign
+        + Mul<Output = T>
+        + Send
+        + Sync
+        + 'static,
+{
+    use rayon::prelude::*;
 
-    for i in 0..a.row {
-        for j in 0..b.col {
+    check_multiply(a, b)?;
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!("multiply", workers = rayon::current_num_threads()).entered();
+    #[cfg(feature = "tracing")]
+    let mut _finished = MultiplyFinished::new(rayon::current_num_threads());
+
+    // each task copies out only its own row and column, so nothing is built up front
+    let data = (0..a.row * b.col)
+        .into_par_iter()
+        .map(|idx| {
+            let (i, j) = (idx / b.col, idx % b.col);
             let row = Vector::new(&a.data[i * a.col..(i + 1) * a.col]);
-            let col_data = b.data[j..]
-                .iter()
-                .step_by(b.col)
-                .copied()
-                .collect::<Vec<_>>();
-            let col = Vector::new(col_data);
+            let col = Vector::new(
+                b.data[j..]
+                    .iter()
+                    .step_by(b.col)
+                    .copied()
+                    .collect::<Vec<_>>(),
+            );
+            // a panicking cell is reported back instead of unwinding through rayon
+            panic::catch_unwind(AssertUnwindSafe(|| dot_product(row, col))).unwrap_or_else(|_| {
+                Err(ConcurrencyError::Worker(format!(
+                    "worker panicked computing cell {}",
+                    idx
+                )))
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    #[cfg(feature = "tracing")]
+    {
+        _finished.messages = data.len();
+    }
 
-            let idx = i * b.col + j;
-            let input = MsgInput::new(idx, row, col);
-            let (tx, rx) = oneshot::channel();
-            let msg = Msg::new(input, tx);
-            if let Err(e) = senders[idx % NUM_THREADS].send(msg) {
-                eprintln!("Send error: {:?}", e);
+    Ok(Matrix {
+        data,
+        row: a.row,
+        col: b.col,
+    })
+}
+
+pub fn multiply_with_threads<T>(
+    a: &Matrix<T>,
+    b: &Matrix<T>,
+    num_threads: usize,
+) -> Result<Matrix<T>>
+where
+    T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
+{
+    check_multiply(a, b)?;
+
+    if num_threads == 0 {
+        return Err(ConcurrencyError::InvalidArgument(
+            "num_threads must be greater than 0".to_string(),
+        ));
+    }
+
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!("multiply", workers = num_threads).entered();
+    #[cfg(feature = "tracing")]
+    let mut _finished = MultiplyFinished::new(num_threads);
+
+    // no need to pay for spawning and channels when there is only one worker
+    if num_threads == 1 {
+        return multiply_sequential(a, b);
+    }
+
+    let workers = Workers::spawn(num_threads, None);
+
+    let mut data = vec![T::default(); a.row * b.col];
+    let _messages = dispatch_into(a, b, &workers.senders, &mut data)?;
+    #[cfg(feature = "tracing")]
+    {
+        _finished.messages = _messages;
+    }
+
+    Ok(Matrix {
+        data,
+        row: a.row,
+        col: b.col,
+    })
+}
+
+/// Like [`multiply`], but workers check `cancel` between cells and the call
+/// returns [`ConcurrencyError::Cancelled`] once it is set. All worker threads
+/// are joined before returning.
+pub fn multiply_cancellable<T>(
+    a: &Matrix<T>,
+    b: &Matrix<T>,
+    cancel: Arc<AtomicBool>,
+) -> Result<Matrix<T>>
+where
+    T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
+{
+    check_multiply(a, b)?;
+    if cancel.load(Ordering::Relaxed) {
+        return Err(ConcurrencyError::Cancelled);
+    }
+
+    let num_threads = default_num_threads();
+    let workers = Workers::spawn(num_threads, Some(cancel.clone()));
+    let result = dispatch(a, b, &workers.senders);
+    drop(workers);
+
+    match result {
+        Err(_) if cancel.load(Ordering::Relaxed) => Err(ConcurrencyError::Cancelled),
+        result => result,
+    }
+}
+
+/// Multiplies `In` matrices into an `Out` result, widening every element before
+/// it is accumulated so e.g. `i8` inputs can't overflow an `i32` sum.
+pub fn multiply_as<In, Out>(a: &Matrix<In>, b: &Matrix<In>) -> Result<Matrix<Out>>
+where
+    In: Debug + Copy,
+    Out: Debug
+        + Copy
+        + Default
+        + Add<Output = Out>
+        + AddAssign
+        + Mul<Output = Out>
+        + Send
+        + MultiplySync
+        + 'static
+        + From<In>,
+{
+    check_multiply(a, b)?;
+    multiply(&a.map(|&v| Out::from(v)), &b.map(|&v| Out::from(v)))
+}
+
+/// Like [`multiply`], but consumes its inputs and writes the result into `a`'s
+/// buffer, which avoids an allocation whenever it is large enough.
+pub fn multiply_owned<T>(a: Matrix<T>, b: Matrix<T>) -> Result<Matrix<T>>
+where
+    T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
+{
+    check_multiply(&a, &b)?;
+
+    let num_threads = default_num_threads();
+    let workers = Workers::spawn(num_threads, None);
+
+    let receivers = send_cells(&a, &b, &workers.senders)?;
+    let (row, col) = (a.row, b.col);
+    let mut data = a.data;
+    data.clear();
+    data.resize(row * col, T::default());
+    collect_cells(receivers, &mut data)?;
+
+    Ok(Matrix { data, row, col })
+}
+
+/// Like [`multiply`], but gives up with [`ConcurrencyError::Timeout`] if the
+/// whole result isn't in within `timeout`. On a timeout the workers are told to
+/// stop and joined before returning, so no thread outlives the call; it can
+/// overrun the deadline by the one cell each worker is still on.
+pub fn multiply_with_timeout<T>(
+    a: &Matrix<T>,
+    b: &Matrix<T>,
+    timeout: Duration,
+) -> Result<Matrix<T>>
+where
+    T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
+{
+    check_multiply(a, b)?;
+
+    let deadline = Instant::now() + timeout;
+    let workers = Workers::spawn(default_num_threads(), None);
+
+    let mut data = vec![T::default(); a.row * b.col];
+    for rx in send_cells(a, b, &workers.senders)? {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let output = match rx.recv_timeout(remaining) {
+            Ok(output) => output?,
+            // dropping `workers` stops and joins them
+            Err(oneshot::RecvTimeoutError::Timeout) => {
+                return Err(ConcurrencyError::Timeout(timeout))
             }
-            receivers.push(rx);
+            Err(e) => {
+                return Err(ConcurrencyError::Worker(format!(
+                    "worker dropped result: {}",
+                    e
+                )))
+            }
+        };
+        data[output.idx] = output.value;
+    }
+
+    Ok(Matrix {
+        data,
+        row: a.row,
+        col: b.col,
+    })
+}
+
+/// What a [`multiply_with_stats`] call did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MultiplyStats {
+    pub threads: usize,
+    pub messages: usize,
+    pub elapsed: Duration,
+}
+
+/// Like [`multiply`] on the channel-based workers, also reporting how many
+/// threads and messages it used and how long it took.
+pub fn multiply_with_stats<T>(a: &Matrix<T>, b: &Matrix<T>) -> Result<(Matrix<T>, MultiplyStats)>
+where
+    T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
+{
+    check_multiply(a, b)?;
+
+    let start = Instant::now();
+    let threads = default_num_threads();
+    let workers = Workers::spawn(threads, None);
+
+    let mut data = vec![T::default(); a.row * b.col];
+    let messages = dispatch_into(a, b, &workers.senders, &mut data)?;
+    let stats = MultiplyStats {
+        threads,
+        messages,
+        elapsed: start.elapsed(),
+    };
+
+    let m = Matrix {
+        data,
+        row: a.row,
+        col: b.col,
+    };
+    Ok((m, stats))
+}
+
+/// Multiplies `a` and `b` into `out`, reusing its allocation. `out` must
+/// already be `a.row x b.col`.
+pub fn multiply_into<T>(a: &Matrix<T>, b: &Matrix<T>, out: &mut Matrix<T>) -> Result<()>
+where
+    T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
+{
+    check_multiply(a, b)?;
+    check_output(a, b, out)?;
+
+    let num_threads = default_num_threads();
+    if num_threads == 1 {
+        return multiply_sequential_into(a, b, &mut out.data);
+    }
+
+    let workers = Workers::spawn(num_threads, None);
+
+    dispatch_into(a, b, &workers.senders, &mut out.data)?;
+    Ok(())
+}
+
+#[cfg(feature = "tokio")]
+pub async fn multiply_async<T>(a: &Matrix<T>, b: &Matrix<T>) -> Result<Matrix<T>>
+where
+    T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
+{
+    check_multiply(a, b)?;
+
+    let mut handles = Vec::with_capacity(a.row * b.col);
+    for i in 0..a.row {
+        for j in 0..b.col {
+            let row = Vector::new(&a.data[i * a.col..(i + 1) * a.col]);
+            let col_data = b.data[j..]
+                .iter()
+                .step_by(b.col)
+                .copied()
+                .collect::<Vec<_>>();
+            let col = Vector::new(col_data);
+            handles.push(tokio::task::spawn_blocking(move || dot_product(row, col)));
         }
     }
 
-    for rx in receivers {
-        let output = rx.recv()?;
-        data[output.idx] = output.value;
-        // println!(
-        //     "data {:?}, output idx = {:?}, output value = {:?}",
-        //     data, output.idx, output.value
-        // );
+    let mut data = Vec::with_capacity(handles.len());
+    for handle in handles {
+        let value = handle
+            .await
+            .map_err(|e| ConcurrencyError::Worker(format!("task join error {}", e)))??;
+        data.push(value);
     }
 
     Ok(Matrix {
@@ -151,68 +1253,831 @@ where
     })
 }
 
-impl<T> MsgInput<T> {
-    pub fn new(idx: usize, row: Vector<T>, col: Vector<T>) -> Self {
-        Self { idx, row, col }
+/// Strassen's algorithm: 7 half-size multiplies per level instead of 8. Only
+/// square matrices whose size is a power of two are split; anything else, and
+/// every block at or below `STRASSEN_THRESHOLD`, goes through `multiply`.
+pub fn multiply_strassen<T>(a: &Matrix<T>, b: &Matrix<T>) -> Result<Matrix<T>>
+where
+    T: Debug
+        + Copy
+        + Default
+        + Add<Output = T>
+        + AddAssign
+        + Sub<Output = T>
+        + Mul<Output = T>
+        + Send
+        + MultiplySync
+        + 'static,
+{
+    check_multiply(a, b)?;
+
+    let n = a.row;
+    let splittable = a.col == n && b.col == n && n.is_power_of_two();
+    if !splittable || n <= STRASSEN_THRESHOLD {
+        return multiply(a, b);
     }
+
+    let h = n / 2;
+    let quadrants = |m: &Matrix<T>| -> Result<[Matrix<T>; 4]> {
+        Ok([
+            m.submatrix(0..h, 0..h)?,
+            m.submatrix(0..h, h..n)?,
+            m.submatrix(h..n, 0..h)?,
+            m.submatrix(h..n, h..n)?,
+        ])
+    };
+    let [a11, a12, a21, a22] = quadrants(a)?;
+    let [b11, b12, b21, b22] = quadrants(b)?;
+
+    let m1 = multiply_strassen(&add(&a11, &a22)?, &add(&b11, &b22)?)?;
+    let m2 = multiply_strassen(&add(&a21, &a22)?, &b11)?;
+    let m3 = multiply_strassen(&a11, &subtract(&b12, &b22)?)?;
+    let m4 = multiply_strassen(&a22, &subtract(&b21, &b11)?)?;
+    let m5 = multiply_strassen(&add(&a11, &a12)?, &b22)?;
+    let m6 = multiply_strassen(&subtract(&a21, &a11)?, &add(&b11, &b12)?)?;
+    let m7 = multiply_strassen(&subtract(&a12, &a22)?, &add(&b21, &b22)?)?;
+
+    let c11 = add(&subtract(&add(&m1, &m4)?, &m5)?, &m7)?;
+    let c12 = add(&m3, &m5)?;
+    let c21 = add(&m2, &m4)?;
+    let c22 = add(&add(&subtract(&m1, &m2)?, &m3)?, &m6)?;
+
+    vstack(&hstack(&c11, &c12)?, &hstack(&c21, &c22)?)
 }
 
-impl<T> Msg<T> {
-    pub fn new(input: MsgInput<T>, sender: oneshot::Sender<MsgOutput<T>>) -> Self {
-        Self { input, sender }
+/// Like `multiply_with_threads`, but each worker's queue holds at most `bound`
+/// pending messages, so dispatch blocks instead of buffering every cell up front.
+pub fn multiply_bounded<T>(
+    a: &Matrix<T>,
+    b: &Matrix<T>,
+    num_threads: usize,
+    bound: usize,
+) -> Result<Matrix<T>>
+where
+    T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
+{
+    check_multiply(a, b)?;
+
+    if num_threads == 0 {
+        return Err(ConcurrencyError::InvalidArgument(
+            "num_threads must be greater than 0".to_string(),
+        ));
+    }
+
+    if num_threads == 1 {
+        return multiply_sequential(a, b);
     }
+
+    let workers = Workers::spawn_bounded(num_threads, bound);
+
+    dispatch(a, b, &workers.senders)
 }
 
-impl<T> Mul for Matrix<T>
+/// Multiplies `a` and `b` by splitting the output into `block_size` x `block_size`
+/// tiles, so each worker computes a whole tile instead of a single cell.
+pub fn multiply_blocked<T>(a: &Matrix<T>, b: &Matrix<T>, block_size: usize) -> Result<Matrix<T>>
+where
+    T: Debug
+        + Copy
+        + Default
+        + Add<Output = T>
+        + AddAssign
+        + Mul<Output = T>
+        + Send
+        + Sync
+        + 'static,
+{
+    check_multiply(a, b)?;
+    if block_size == 0 {
+        return Err(ConcurrencyError::InvalidArgument(
+            "block_size must be greater than 0".to_string(),
+        ));
+    }
+    if a.col == 0 && a.row * b.col > 0 {
+        return Err(ConcurrencyError::EmptyInput);
+    }
+
+    // columns of b become contiguous rows, which keeps the inner loop cache friendly
+    let bt = b.transpose();
+    let tiles = (0..a.row)
+        .step_by(block_size)
+        .flat_map(|i| (0..b.col).step_by(block_size).map(move |j| (i, j)))
+        .collect::<Vec<_>>();
+    let num_threads = default_num_threads().min(tiles.len().max(1));
+
+    let mut data = vec![T::default(); a.row * b.col];
+    let results = thread::scope(|s| {
+        let handles = (0..num_threads)
+            .map(|worker| {
+                let (a, bt, tiles) = (a, &bt, &tiles);
+                s.spawn(move || {
+                    tiles
+                        .iter()
+                        .skip(worker)
+                        .step_by(num_threads)
+                        .map(|&(i0, j0)| {
+                            let i1 = (i0 + block_size).min(a.row);
+                            let j1 = (j0 + block_size).min(bt.row);
+                            let mut tile = Vec::with_capacity((i1 - i0) * (j1 - j0));
+                            for i in i0..i1 {
+                                let row = &a.data[i * a.col..(i + 1) * a.col];
+                                for j in j0..j1 {
+                                    let col = &bt.data[j * bt.col..(j + 1) * bt.col];
+                                    let mut sum = T::default();
+                                    for k in 0..a.col {
+                                        sum += row[k] * col[k];
+                                    }
+                                    tile.push(sum);
+                                }
+                            }
+                            ((i0, i1, j0, j1), tile)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        join_all(handles)
+    })?;
+
+    for ((i0, i1, j0, j1), tile) in results.into_iter().flatten() {
+        let width = j1 - j0;
+        for i in i0..i1 {
+            let start = (i - i0) * width;
+            data[i * b.col + j0..i * b.col + j1].copy_from_slice(&tile[start..start + width]);
+        }
+    }
+
+    Ok(Matrix {
+        data,
+        row: a.row,
+        col: b.col,
+    })
+}
+
+/// Multiplies `a` and `b` with overflow-checked arithmetic, failing with
+/// [`ConcurrencyError::Overflow`] instead of wrapping.
+pub fn multiply_checked<T>(a: &Matrix<T>, b: &Matrix<T>) -> Result<Matrix<T>>
+where
+    T: Debug + CheckedNum,
+{
+    check_multiply(a, b)?;
+
+    let mut data = Vec::with_capacity(a.row * b.col);
+    for i in 0..a.row {
+        let row = Vector::new(&a.data[i * a.col..(i + 1) * a.col]);
+        for j in 0..b.col {
+            let col = b.data[j..].iter().step_by(b.col).copied().collect();
+            data.push(dot_product_checked(&row, &col)?);
+        }
+    }
+
+    Ok(Matrix {
+        data,
+        row: a.row,
+        col: b.col,
+    })
+}
+
+/// Multiplies `a` and `b` for element types that are `Clone` but not `Copy`,
+/// cloning each row and column into its own `Vector` before the dot product.
+pub fn multiply_ref<T>(a: &Matrix<T>, b: &Matrix<T>) -> Result<Matrix<T>>
+where
+    T: Debug + Clone + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + Sync,
+{
+    check_multiply(a, b)?;
+    if a.col == 0 && a.row * b.col > 0 {
+        return Err(ConcurrencyError::EmptyInput);
+    }
+
+    let num_threads = default_num_threads().min(a.row.max(1));
+
+    let rows = thread::scope(|s| {
+        let handles = (0..num_threads)
+            .map(|worker| {
+                s.spawn(move || {
+                    (worker..a.row)
+                        .step_by(num_threads)
+                        .map(|i| {
+                            let row = Vector::new(a.data[i * a.col..(i + 1) * a.col].to_vec());
+                            let values = (0..b.col)
+                                .map(|j| {
+                                    let col = b.data[j..]
+                                        .iter()
+                                        .step_by(b.col)
+                                        .cloned()
+                                        .collect::<Vector<_>>();
+                                    row.iter().zip(col).fold(T::default(), |mut sum, (x, y)| {
+                                        sum += x.clone() * y;
+                                        sum
+                                    })
+                                })
+                                .collect::<Vec<_>>();
+                            (i, values)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        join_all(handles)
+    })?;
+
+    let mut rows = rows.into_iter().flatten().collect::<Vec<_>>();
+    rows.sort_by_key(|(i, _)| *i);
+    let data = rows.into_iter().flat_map(|(_, values)| values).collect();
+
+    Ok(Matrix {
+        data,
+        row: a.row,
+        col: b.col,
+    })
+}
+
+/// Multiplies matrices on a fixed set of long-lived worker threads, so repeated
+/// multiplies don't pay the spawn/join cost every time.
+pub struct MatrixMultiplier<T> {
+    workers: Workers<mpsc::Sender<Msg<T>>>,
+}
+
+impl<T> MatrixMultiplier<T>
 where
     T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
 {
-    type Output = Self;
+    pub fn new(num_threads: usize) -> Result<Self> {
+        if num_threads == 0 {
+            return Err(ConcurrencyError::InvalidArgument(
+                "num_threads must be greater than 0".to_string(),
+            ));
+        }
 
-    fn mul(self, rhs: Self) -> Self::Output {
-        multiply(&self, &rhs).expect("Multiply error")
+        Ok(Self {
+            workers: Workers::spawn(num_threads, None),
+        })
+    }
+
+    pub fn num_threads(&self) -> usize {
+        self.workers.handles.len()
+    }
+
+    pub fn multiply(&self, a: &Matrix<T>, b: &Matrix<T>) -> Result<Matrix<T>> {
+        check_multiply(a, b)?;
+        dispatch(a, b, &self.workers.senders)
+    }
+
+    pub fn multiply_into(&self, a: &Matrix<T>, b: &Matrix<T>, out: &mut Matrix<T>) -> Result<()> {
+        check_multiply(a, b)?;
+        check_output(a, b, out)?;
+        dispatch_into(a, b, &self.workers.senders, &mut out.data)?;
+        Ok(())
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use anyhow::Result;
+/// Owns a set of worker threads and their senders. Dropping it raises `stop`,
+/// closes every sender and joins the workers, so no exit path from a multiply
+/// leaves threads behind or keeps them busy on cells nobody will read.
+struct Workers<S> {
+    senders: Vec<S>,
+    handles: Vec<JoinHandle<Result<()>>>,
+    stop: Arc<AtomicBool>,
+}
 
-    #[test]
-    fn test_multiply() -> Result<()> {
-        let a = Matrix::new(
-            // [[1,2,3]
-            // [4,5,6]]
-            vec![1, 2, 3, 4, 5, 6],
-            2,
-            3,
-        );
-        let b = Matrix::new(
-            // [[1,2]
-            // [3,4]
-            // [5,6]]
-            vec![1, 2, 3, 4, 5, 6],
-            3,
-            2,
-        );
-        // [[22,28]
-        // [49,64]]
-        let ret = multiply(&a, &b)?;
-        assert_eq!(ret, Matrix::new(vec![22, 28, 49, 64], 2, 2));
-        assert_eq!(format!("{}", ret), "{22 28, 49 64}");
-        assert_eq!(format!("{:?}", ret), "Matrix(row=2, col=2, {22 28, 49 64})");
+impl<T> Workers<mpsc::Sender<Msg<T>>>
+where
+    T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
+{
+    fn spawn(num_threads: usize, cancel: Option<Arc<AtomicBool>>) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (senders, handles) = (0..num_threads)
+            .map(|_| {
+                let (tx, rx) = mpsc::channel::<Msg<T>>();
+                (
+                    tx,
+                    spawn_cancellable_worker(rx, stop.clone(), cancel.clone()),
+                )
+            })
+            .unzip();
+        Self {
+            senders,
+            handles,
+            stop,
+        }
+    }
+}
 
-        let ret = a * b;
-        assert_eq!(ret, Matrix::new(vec![22, 28, 49, 64], 2, 2));
-        assert_eq!(format!("{}", ret), "{22 28, 49 64}");
-        assert_eq!(format!("{:?}", ret), "Matrix(row=2, col=2, {22 28, 49 64})");
+impl<T> Workers<mpsc::SyncSender<Msg<T>>>
+where
+    T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
+{
+    fn spawn_bounded(num_threads: usize, bound: usize) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (senders, handles) = (0..num_threads)
+            .map(|_| {
+                let (tx, rx) = mpsc::sync_channel::<Msg<T>>(bound);
+                (tx, spawn_worker(rx, stop.clone()))
+            })
+            .unzip();
+        Self {
+            senders,
+            handles,
+            stop,
+        }
+    }
+}
+
+impl<S> Drop for Workers<S> {
+    fn drop(&mut self) {
+        // raising `stop` makes the workers skip whatever is still queued, and
+        // closing every sender ends their receive loops
+        self.stop.store(true, Ordering::Relaxed);
+        self.senders.clear();
+        for worker in self.handles.drain(..) {
+            // a drop has nowhere to return an error to, so it is only traced
+            let _result = worker.join();
+            #[cfg(feature = "tracing")]
+            match _result {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => tracing::debug!(error = %e, "worker stopped early"),
+                Err(e) => tracing::warn!(error = ?e, "worker join error"),
+            }
+        }
+    }
+}
+
+fn spawn_worker<T>(rx: mpsc::Receiver<Msg<T>>, stop: Arc<AtomicBool>) -> JoinHandle<Result<()>>
+where
+    T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
+{
+    spawn_cancellable_worker(rx, stop, None)
+}
+
+fn spawn_cancellable_worker<T>(
+    rx: mpsc::Receiver<Msg<T>>,
+    stop: Arc<AtomicBool>,
+    cancel: Option<Arc<AtomicBool>>,
+) -> JoinHandle<Result<()>>
+where
+    T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
+{
+    thread::spawn(move || {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("worker").entered();
+        for msg in rx {
+            // the owning `Workers` is gone, so nobody is waiting on what's left
+            if stop.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            // dropping the queued messages makes the caller's receivers fail fast
+            if cancel.as_ref().is_some_and(|c| c.load(Ordering::Relaxed)) {
+                return Err(ConcurrencyError::Cancelled);
+            }
+            let idx = msg.input.idx;
+            let (row, col) = (msg.input.row, msg.input.col);
+            // a panicking cell is reported back instead of taking the worker down with it
+            let output = panic::catch_unwind(AssertUnwindSafe(|| dot_product(row, col)))
+                .unwrap_or_else(|_| {
+                    Err(ConcurrencyError::Worker(format!(
+                        "worker panicked computing cell {}",
+                        idx
+                    )))
+                })
+                .map(|value| MsgOutput { idx, value });
+            // the receiver is only gone if the caller already gave up on this multiply
+            let _ = msg.sender.send(output);
+        }
         Ok(())
+    })
+}
+
+trait MsgSender<T> {
+    fn send_msg(&self, msg: Msg<T>) -> Result<(), mpsc::SendError<Msg<T>>>;
+}
+
+impl<T> MsgSender<T> for mpsc::Sender<Msg<T>> {
+    fn send_msg(&self, msg: Msg<T>) -> Result<(), mpsc::SendError<Msg<T>>> {
+        self.send(msg)
     }
+}
 
-    #[test]
-    #[should_panic]
-    fn test_a_can_not_multiply_b() {
+impl<T> MsgSender<T> for mpsc::SyncSender<Msg<T>> {
+    fn send_msg(&self, msg: Msg<T>) -> Result<(), mpsc::SendError<Msg<T>>> {
+        self.send(msg)
+    }
+}
+
+fn dispatch<T, S>(a: &Matrix<T>, b: &Matrix<T>, senders: &[S]) -> Result<Matrix<T>>
+where
+    T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
+    S: MsgSender<T>,
+{
+    let mut data = vec![T::default(); a.row * b.col];
+    dispatch_into(a, b, senders, &mut data)?;
+
+    Ok(Matrix {
+        data,
+        row: a.row,
+        col: b.col,
+    })
+}
+
+// returns the number of messages sent to the workers
+fn dispatch_into<T, S>(a: &Matrix<T>, b: &Matrix<T>, senders: &[S], data: &mut [T]) -> Result<usize>
+where
+    T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
+    S: MsgSender<T>,
+{
+    let receivers = send_cells(a, b, senders)?;
+    let messages = receivers.len();
+    collect_cells(receivers, data)?;
+    Ok(messages)
+}
+
+type CellReceiver<T> = oneshot::Receiver<Result<MsgOutput<T>>>;
+
+// every row and column is copied into its message, so once this returns the
+// inputs are no longer needed
+fn send_cells<T, S>(a: &Matrix<T>, b: &Matrix<T>, senders: &[S]) -> Result<Vec<CellReceiver<T>>>
+where
+    T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
+    S: MsgSender<T>,
+{
+    let matrix_len = a.row * b.col;
+    let mut receivers = Vec::with_capacity(matrix_len);
+    let mut alive = vec![true; senders.len()];
+
+    for i in 0..a.row {
+        for j in 0..b.col {
+            let row = Vector::new(&a.data[i * a.col..(i + 1) * a.col]);
+            let col_data = b.data[j..]
+                .iter()
+                .step_by(b.col)
+                .copied()
+                .collect::<Vec<_>>();
+            let col = Vector::new(col_data);
+
+            let idx = i * b.col + j;
+            let input = MsgInput::new(idx, row, col);
+            let (tx, rx) = oneshot::channel();
+            send_to_live_worker(senders, &mut alive, idx, Msg::new(input, tx))?;
+            receivers.push(rx);
+        }
+    }
+
+    Ok(receivers)
+}
+
+// a send only fails once a worker has exited, so its cells are handed to the
+// next live worker instead and it is skipped from then on
+fn send_to_live_worker<T, S: MsgSender<T>>(
+    senders: &[S],
+    alive: &mut [bool],
+    idx: usize,
+    mut msg: Msg<T>,
+) -> Result<()> {
+    let n = senders.len();
+    for w in (0..n).map(|k| (idx + k) % n) {
+        if !alive[w] {
+            continue;
+        }
+        match senders[w].send_msg(msg) {
+            Ok(()) => return Ok(()),
+            Err(mpsc::SendError(m)) => {
+                alive[w] = false;
+                msg = m;
+            }
+        }
+    }
+    Err(ConcurrencyError::Worker(format!(
+        "all {} workers are gone, cell {} could not be dispatched",
+        n, idx
+    )))
+}
+
+fn collect_cells<T>(receivers: Vec<CellReceiver<T>>, data: &mut [T]) -> Result<()> {
+    for rx in receivers {
+        let output = rx
+            .recv()
+            .map_err(|e| ConcurrencyError::Worker(format!("worker dropped result: {}", e)))??;
+        data[output.idx] = output.value;
+    }
+
+    Ok(())
+}
+
+pub fn add<T>(a: &Matrix<T>, b: &Matrix<T>) -> Result<Matrix<T>>
+where
+    T: Debug + Copy + Add<Output = T>,
+{
+    if a.row != b.row || a.col != b.col {
+        return Err(ConcurrencyError::DimensionMismatch {
+            expected: (a.row, a.col),
+            got: (b.row, b.col),
+        });
+    }
+
+    let data = a.data.iter().zip(b.data.iter()).map(|(&x, &y)| x + y);
+    Ok(Matrix::new(data.collect::<Vec<_>>(), a.row, a.col))
+}
+
+pub fn subtract<T>(a: &Matrix<T>, b: &Matrix<T>) -> Result<Matrix<T>>
+where
+    T: Debug + Copy + Sub<Output = T>,
+{
+    if a.row != b.row || a.col != b.col {
+        return Err(ConcurrencyError::DimensionMismatch {
+            expected: (a.row, a.col),
+            got: (b.row, b.col),
+        });
+    }
+
+    let data = a.data.iter().zip(b.data.iter()).map(|(&x, &y)| x - y);
+    Ok(Matrix::new(data.collect::<Vec<_>>(), a.row, a.col))
+}
+
+pub fn hadamard<T>(a: &Matrix<T>, b: &Matrix<T>) -> Result<Matrix<T>>
+where
+    T: Debug + Copy + Mul<Output = T>,
+{
+    if a.row != b.row || a.col != b.col {
+        return Err(ConcurrencyError::DimensionMismatch {
+            expected: (a.row, a.col),
+            got: (b.row, b.col),
+        });
+    }
+
+    let data = a.data.iter().zip(b.data.iter()).map(|(&x, &y)| x * y);
+    Ok(Matrix::new(data.collect::<Vec<_>>(), a.row, a.col))
+}
+
+/// Solves `a * x = b` through [`Matrix::lu`] and forward/back substitution.
+pub fn solve<T>(a: &Matrix<T>, b: &Vector<T>) -> Result<Vector<T>>
+where
+    T: Debug + Float,
+{
+    let (l, u, perm) = a.lu()?;
+    let n = a.row;
+    if b.len() != n {
+        return Err(ConcurrencyError::LengthMismatch {
+            expected: n,
+            got: b.len(),
+        });
+    }
+
+    // L * y = P * b
+    let mut y = vec![T::zero(); n];
+    for i in 0..n {
+        let sum = (0..i).fold(T::zero(), |acc, k| acc + l.data[i * n + k] * y[k]);
+        y[i] = b[perm[i]] - sum;
+    }
+    // U * x = y
+    let mut x = vec![T::zero(); n];
+    for i in (0..n).rev() {
+        let sum = (i + 1..n).fold(T::zero(), |acc, k| acc + u.data[i * n + k] * x[k]);
+        x[i] = (y[i] - sum) / u.data[i * n + i];
+    }
+    Ok(Vector::new(x))
+}
+
+/// The Kronecker product: every `a[i, j]` becomes the block `a[i, j] * b`.
+pub fn kron<T>(a: &Matrix<T>, b: &Matrix<T>) -> Matrix<T>
+where
+    T: Debug + Copy + Mul<Output = T>,
+{
+    let (row, col) = (a.row * b.row, a.col * b.col);
+    let mut data = Vec::with_capacity(row * col);
+    for i in 0..row {
+        let (ai, bi) = (i / b.row, i % b.row);
+        for j in 0..col {
+            let (aj, bj) = (j / b.col, j % b.col);
+            data.push(a.data[ai * a.col + aj] * b.data[bi * b.col + bj]);
+        }
+    }
+    Matrix::new(data, row, col)
+}
+
+pub fn hstack<T>(a: &Matrix<T>, b: &Matrix<T>) -> Result<Matrix<T>>
+where
+    T: Debug + Clone,
+{
+    if a.row != b.row {
+        return Err(ConcurrencyError::DimensionMismatch {
+            expected: (a.row, b.col),
+            got: (b.row, b.col),
+        });
+    }
+
+    let mut data = Vec::with_capacity(a.data.len() + b.data.len());
+    for (x, y) in a.rows().zip(b.rows()) {
+        data.extend_from_slice(x);
+        data.extend_from_slice(y);
+    }
+    Ok(Matrix::new(data, a.row, a.col + b.col))
+}
+
+pub fn vstack<T>(a: &Matrix<T>, b: &Matrix<T>) -> Result<Matrix<T>>
+where
+    T: Debug + Clone,
+{
+    if a.col != b.col {
+        return Err(ConcurrencyError::DimensionMismatch {
+            expected: (b.row, a.col),
+            got: (b.row, b.col),
+        });
+    }
+
+    let mut data = Vec::with_capacity(a.data.len() + b.data.len());
+    data.extend_from_slice(&a.data);
+    data.extend_from_slice(&b.data);
+    Ok(Matrix::new(data, a.row + b.row, a.col))
+}
+
+// emits the "multiply finished" event when dropped, so every return path from
+// a multiply reports it; `messages` stays 0 unless cells went to workers
+#[cfg(feature = "tracing")]
+struct MultiplyFinished {
+    workers: usize,
+    messages: usize,
+    start: Instant,
+}
+
+#[cfg(feature = "tracing")]
+impl MultiplyFinished {
+    fn new(workers: usize) -> Self {
+        Self {
+            workers,
+            messages: 0,
+            start: Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for MultiplyFinished {
+    fn drop(&mut self) {
+        tracing::debug!(
+            workers = self.workers,
+            messages = self.messages,
+            elapsed_us = self.start.elapsed().as_micros() as u64,
+            "multiply finished"
+        );
+    }
+}
+
+// joins every scoped thread before looking at any result: a panicked thread
+// left unjoined would make the scope itself panic
+pub(crate) fn join_all<R>(handles: Vec<thread::ScopedJoinHandle<'_, R>>) -> Result<Vec<R>> {
+    let results = handles.into_iter().map(|h| h.join()).collect::<Vec<_>>();
+    results
+        .into_iter()
+        .map(|r| r.map_err(|e| ConcurrencyError::Worker(format!("thread join error {:?}", e))))
+        .collect()
+}
+
+/// Worker count used when the caller doesn't pick one: the machine's
+/// available parallelism, or `NUM_THREADS` if that can't be determined.
+pub(crate) fn default_num_threads() -> usize {
+    thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(NUM_THREADS)
+}
+
+fn check_multiply<T: Debug>(a: &Matrix<T>, b: &Matrix<T>) -> Result<()> {
+    if a.col != b.row {
+        return Err(ConcurrencyError::DimensionMismatch {
+            expected: (a.col, b.col),
+            got: (b.row, b.col),
+        });
+    }
+    Ok(())
+}
+
+fn check_output<T: Debug>(a: &Matrix<T>, b: &Matrix<T>, out: &Matrix<T>) -> Result<()> {
+    if out.row != a.row || out.col != b.col {
+        return Err(ConcurrencyError::DimensionMismatch {
+            expected: (a.row, b.col),
+            got: (out.row, out.col),
+        });
+    }
+    Ok(())
+}
+
+fn multiply_sequential<T>(a: &Matrix<T>, b: &Matrix<T>) -> Result<Matrix<T>>
+where
+    T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
+{
+    let mut data = vec![T::default(); a.row * b.col];
+    multiply_sequential_into(a, b, &mut data)?;
+
+    Ok(Matrix {
+        data,
+        row: a.row,
+        col: b.col,
+    })
+}
+
+fn multiply_sequential_into<T>(a: &Matrix<T>, b: &Matrix<T>, data: &mut [T]) -> Result<()>
+where
+    T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
+{
+    // columns of b become contiguous rows, so no cell needs its own allocation
+    let bt = b.transpose();
+    for i in 0..a.row {
+        let row = &a.data[i * a.col..(i + 1) * a.col];
+        for j in 0..b.col {
+            let col = &bt.data[j * bt.col..(j + 1) * bt.col];
+            data[i * b.col + j] = dot_slice(row, col)?;
+        }
+    }
+    Ok(())
+}
+
+impl<T> MsgInput<T> {
+    pub fn new(idx: usize, row: Vector<T>, col: Vector<T>) -> Self {
+        Self { idx, row, col }
+    }
+}
+
+impl<T> Msg<T> {
+    pub fn new(input: MsgInput<T>, sender: oneshot::Sender<Result<MsgOutput<T>>>) -> Self {
+        Self { input, sender }
+    }
+}
+
+impl<T> Mul for Matrix<T>
+where
+    T: Debug
+        + Copy
+        + Default
+        + Add<Output = T>
+        + AddAssign
+        + Mul<Output = T>
+        + Send
+        + MultiplySync
+        + 'static,
+{
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        multiply(&self, &rhs).expect("Multiply error")
+    }
+}
+
+impl<T> Mul<T> for Matrix<T>
+where
+    T: Debug + Copy + Mul<Output = T>,
+{
+    type Output = Self;
+
+    fn mul(self, rhs: T) -> Self::Output {
+        self.scale(rhs)
+    }
+}
+
+impl<T> Mul<Vector<T>> for Matrix<T>
+where
+    T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
+{
+    type Output = Vector<T>;
+
+    fn mul(self, rhs: Vector<T>) -> Self::Output {
+        self.mul_vector(&rhs).expect("Multiply error")
+    }
+}
+
+impl<T> Add for Matrix<T>
+where
+    T: Debug + Copy + Add<Output = T>,
+{
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        add(&self, &rhs).expect("Add error")
+    }
+}
+
+impl<T> Sub for Matrix<T>
+where
+    T: Debug + Copy + Sub<Output = T>,
+{
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        subtract(&self, &rhs).expect("Subtract error")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{fixture, Big, Bomb, Slow, Tick, SLOW_RUNNING, TICKS};
+    use anyhow::Result;
+    use std::{
+        collections::HashSet,
+        sync::{atomic::AtomicUsize, Mutex},
+    };
+
+    #[test]
+    fn test_multiply() -> Result<()> {
         let a = Matrix::new(
             // [[1,2,3]
             // [4,5,6]]
@@ -223,12 +2088,1213 @@ mod tests {
         let b = Matrix::new(
             // [[1,2]
             // [3,4]
-            vec![1, 2, 3, 4],
-            2,
+            // [5,6]]
+            vec![1, 2, 3, 4, 5, 6],
+            3,
             2,
         );
-        // let _ret = multiply(&a, &b);
-        let _ret = a * b;
-        // assert!(ret.is_err());
+        // [[22,28]
+        // [49,64]]
+        let ret = multiply(&a, &b)?;
+        assert_eq!(ret, Matrix::new(vec![22, 28, 49, 64], 2, 2));
+        assert_eq!(format!("{}", ret), "{22 28, 49 64}");
+        assert_eq!(format!("{:?}", ret), "Matrix(row=2, col=2, {22 28, 49 64})");
+
+        let ret = a * b;
+        assert_eq!(ret, Matrix::new(vec![22, 28, 49, 64], 2, 2));
+        assert_eq!(format!("{}", ret), "{22 28, 49 64}");
+        assert_eq!(format!("{:?}", ret), "Matrix(row=2, col=2, {22 28, 49 64})");
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiply_with_threads() -> Result<()> {
+        let a = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        let b = Matrix::new(vec![1, 2, 3, 4, 5, 6], 3, 2);
+        let expected = Matrix::new(vec![22, 28, 49, 64], 2, 2);
+
+        for num_threads in [1, 2, 3, 8] {
+            let ret = multiply_with_threads(&a, &b, num_threads)?;
+            assert_eq!(ret, expected);
+        }
+
+        assert!(multiply_with_threads(&a, &b, 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_transpose() {
+        let a = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        let t = a.transpose();
+        assert_eq!(t, Matrix::new(vec![1, 4, 2, 5, 3, 6], 3, 2));
+        assert_eq!(t.transpose(), a);
+
+        let row = Matrix::new(vec![1, 2, 3], 1, 3);
+        assert_eq!(row.transpose(), Matrix::new(vec![1, 2, 3], 3, 1));
+        assert_eq!(row.transpose().transpose(), row);
+    }
+
+    #[test]
+    fn test_add_and_subtract() -> Result<()> {
+        let a = Matrix::new(vec![1, 2, 3, 4], 2, 2);
+        let b = Matrix::new(vec![5, 6, 7, 8], 2, 2);
+        assert_eq!(add(&a, &b)?, Matrix::new(vec![6, 8, 10, 12], 2, 2));
+        assert_eq!(subtract(&b, &a)?, Matrix::new(vec![4, 4, 4, 4], 2, 2));
+
+        assert_eq!(a + b, Matrix::new(vec![6, 8, 10, 12], 2, 2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_dimension_mismatch() {
+        let a = Matrix::new(vec![1, 2, 3, 4], 2, 2);
+        let b = Matrix::new(vec![1, 2, 3, 4], 1, 4);
+        assert_eq!(
+            add(&a, &b),
+            Err(ConcurrencyError::DimensionMismatch {
+                expected: (2, 2),
+                got: (1, 4)
+            })
+        );
+        assert!(subtract(&a, &b).is_err());
+    }
+
+    #[test]
+    fn test_get_and_set() -> Result<()> {
+        let mut a = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        assert_eq!(a.get(0, 1), Some(&2));
+        assert_eq!(a.get(1, 2), Some(&6));
+        assert_eq!(a.get(2, 0), None);
+        assert_eq!(a.get(0, 3), None);
+
+        a.set(1, 2, 60)?;
+        assert_eq!(a.get(1, 2), Some(&60));
+        assert!(a.set(0, 3, 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_matrix_multiplier_reuses_workers() -> Result<()> {
+        let a = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        let b = Matrix::new(vec![1, 2, 3, 4, 5, 6], 3, 2);
+        let expected = Matrix::new(vec![22, 28, 49, 64], 2, 2);
+
+        let multiplier = MatrixMultiplier::new(4)?;
+        for _ in 0..1000 {
+            assert_eq!(multiplier.multiply(&a, &b)?, expected);
+        }
+        assert_eq!(multiplier.num_threads(), 4);
+        assert!(multiplier.multiply(&a, &a).is_err());
+        drop(multiplier);
+
+        assert!(MatrixMultiplier::<i32>::new(0).is_err());
+        Ok(())
+    }
+
+    // remembers which thread computed every product
+    static MUL_THREADS: Mutex<Vec<thread::ThreadId>> = Mutex::new(Vec::new());
+
+    fixture!(
+        #[derive(Clone, Copy)]
+        Traced(i32),
+        |a, b| {
+            MUL_THREADS.lock().unwrap().push(thread::current().id());
+            a * b
+        }
+    );
+
+    #[test]
+    fn test_matrix_multiplier_serves_every_call_from_the_same_threads() -> Result<()> {
+        let a = Matrix::new(vec![Traced(1); 64], 8, 8);
+        let multiplier = MatrixMultiplier::new(4)?;
+
+        let mut seen = HashSet::new();
+        for _ in 0..10 {
+            assert_eq!(multiplier.multiply(&a, &a)?.data, vec![Traced(8); 64]);
+            seen.extend(MUL_THREADS.lock().unwrap().drain(..));
+        }
+        // a pool that spawned per call would have used up to 40 distinct threads
+        assert!(!seen.is_empty() && seen.len() <= multiplier.num_threads());
+        assert!(!seen.contains(&thread::current().id()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_worker_reports_error() -> Result<()> {
+        let (tx, rx) = mpsc::channel::<Msg<i32>>();
+        spawn_worker(rx, Arc::default());
+
+        let input = MsgInput::new(0, Vector::new(vec![1, 2]), Vector::new(vec![1]));
+        let (out_tx, out_rx) = oneshot::channel();
+        tx.send(Msg::new(input, out_tx))
+            .map_err(|_| anyhow::anyhow!("worker is gone"))?;
+
+        let ret = out_rx.recv()?;
+        assert!(ret.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_constructors() -> Result<()> {
+        assert_eq!(Matrix::<i32>::zeros(2, 3), Matrix::new(vec![0; 6], 2, 3));
+        assert_eq!(Matrix::filled(2, 2, 7), Matrix::new(vec![7; 4], 2, 2));
+        assert_eq!(
+            Matrix::<i32>::identity(3),
+            Matrix::new(vec![1, 0, 0, 0, 1, 0, 0, 0, 1], 3, 3)
+        );
+
+        let m = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        let i = Matrix::identity(2);
+        assert_eq!(multiply(&i, &m)?, m);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scale() {
+        let a = Matrix::new(vec![1, 2, 3, 4], 2, 2);
+        assert_eq!(a.scale(3), Matrix::new(vec![3, 6, 9, 12], 2, 2));
+        assert_eq!(a * 3, Matrix::new(vec![3, 6, 9, 12], 2, 2));
+    }
+
+    #[test]
+    fn test_mul_vector() -> Result<()> {
+        let a = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        let v = Vector::new(vec![1, 0, 2]);
+        let ret = a.mul_vector(&v)?;
+        assert_eq!(ret.len(), 2);
+        assert_eq!(*ret, vec![7, 16]);
+
+        assert!(a.mul_vector(&Vector::new(vec![1, 2])).is_err());
+        assert_eq!(*(a * v), vec![7, 16]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_rows() -> Result<()> {
+        let m = Matrix::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]])?;
+        assert_eq!(m, Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3));
+
+        assert!(Matrix::from_rows(vec![vec![1, 2, 3], vec![4, 5]]).is_err());
+        assert!(Matrix::<i32>::from_rows(vec![]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_a_can_not_multiply_b() {
+        let a = Matrix::new(
+            // [[1,2,3]
+            // [4,5,6]]
+            vec![1, 2, 3, 4, 5, 6],
+            2,
+            3,
+        );
+        let b = Matrix::new(
+            // [[1,2]
+            // [3,4]
+            vec![1, 2, 3, 4],
+            2,
+            2,
+        );
+        // let _ret = multiply(&a, &b);
+        let _ret = a * b;
+        // assert!(ret.is_err());
+    }
+
+    #[test]
+    fn test_try_new() -> Result<()> {
+        let m = Matrix::try_new(vec![1, 2, 3, 4, 5, 6], 2, 3)?;
+        assert_eq!(m, Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3));
+        assert!(Matrix::try_new(vec![1, 2, 3, 4, 5], 2, 3).is_err());
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_dimension_mismatch() {
+        let _m = Matrix::new(vec![1, 2, 3], 2, 2);
+    }
+
+    #[test]
+    fn test_clone() {
+        let a = Matrix::new(vec![1, 2, 3, 4], 2, 2);
+        let squared = a.clone() * a.clone();
+        assert_eq!(squared, Matrix::new(vec![7, 10, 15, 22], 2, 2));
+        assert_eq!(a, Matrix::new(vec![1, 2, 3, 4], 2, 2));
+    }
+
+    #[test]
+    fn test_power() -> Result<()> {
+        let a = Matrix::new(vec![1, 2, 3, 4], 2, 2);
+        assert_eq!(a.power(0)?, Matrix::identity(2));
+        assert_eq!(a.power(1)?, a);
+        assert_eq!(a.power(2)?, Matrix::new(vec![7, 10, 15, 22], 2, 2));
+        assert_eq!(a.power(3)?, Matrix::new(vec![37, 54, 81, 118], 2, 2));
+
+        let b = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        assert!(b.power(2).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_hadamard() -> Result<()> {
+        let a = Matrix::new(vec![1, 2, 3, 4], 2, 2);
+        let b = Matrix::new(vec![5, 6, 7, 8], 2, 2);
+        assert_eq!(hadamard(&a, &b)?, Matrix::new(vec![5, 12, 21, 32], 2, 2));
+
+        let c = Matrix::new(vec![1, 2, 3, 4], 4, 1);
+        assert!(hadamard(&a, &c).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rows_and_col() {
+        let m = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        let rows = m.rows().collect::<Vec<_>>();
+        assert_eq!(rows, vec![&[1, 2, 3][..], &[4, 5, 6][..]]);
+
+        assert_eq!(m.col(1), Some(vec![2, 5]));
+        assert_eq!(m.col(3), None);
+    }
+
+    #[test]
+    fn test_approx_eq() {
+        let a = Matrix::new(vec![1.0, 2.0, 3.0, 4.0], 2, 2);
+        let b = Matrix::new(vec![1.0, 2.0 + 1e-9, 3.0, 4.0], 2, 2);
+        assert!(a != b);
+        assert!(a.approx_eq(&b, 1e-6));
+        assert!(!a.approx_eq(&b, 1e-12));
+
+        let c = Matrix::new(vec![1.0, 2.0, 3.0, 4.0], 1, 4);
+        assert!(!a.approx_eq(&c, 1e-6));
+    }
+
+    #[test]
+    fn test_multiply_blocked() -> Result<()> {
+        let a = Matrix::new((0..35).collect::<Vec<i64>>(), 5, 7);
+        let b = Matrix::new((0..42).map(|x| x % 5 - 2).collect::<Vec<i64>>(), 7, 6);
+        let expected = multiply(&a, &b)?;
+
+        for block_size in [1, 2, 3, 4, 16] {
+            assert_eq!(multiply_blocked(&a, &b, block_size)?, expected);
+        }
+
+        assert!(multiply_blocked(&a, &b, 0).is_err());
+        assert!(multiply_blocked(&a, &a, 2).is_err());
+
+        // every worker panics, not just the first one joined
+        let bombs = Matrix::new(vec![Bomb(13); 64], 8, 8);
+        assert!(matches!(
+            multiply_blocked(&bombs, &bombs, 2),
+            Err(ConcurrencyError::Worker(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_map() {
+        let m = Matrix::new(vec![1, -2, 3, -4, 5, -6], 2, 3);
+        assert_eq!(
+            m.map(|x| x * x),
+            Matrix::new(vec![1, 4, 9, 16, 25, 36], 2, 3)
+        );
+        assert_eq!(
+            m.map(|&x| x > 0),
+            Matrix::new(vec![true, false, true, false, true, false], 2, 3)
+        );
+    }
+
+    #[test]
+    fn test_multiply_bounded() -> Result<()> {
+        let a = Matrix::new((0..400).collect::<Vec<i64>>(), 20, 20);
+        let b = Matrix::new((0..400).map(|x| x % 9).collect::<Vec<i64>>(), 20, 20);
+        let expected = multiply(&a, &b)?;
+
+        // a rendezvous channel (bound 0) is the tightest backpressure possible
+        for bound in [0, 1, 4, 1000] {
+            assert_eq!(multiply_bounded(&a, &b, 4, bound)?, expected);
+        }
+        assert!(multiply_bounded(&a, &b, 0, 1).is_err());
+        Ok(())
+    }
+
+    // counts every product as it starts, so a cell of a 1-deep multiply is
+    // started exactly when its worker picks it up
+    static STARTED: AtomicUsize = AtomicUsize::new(0);
+
+    fixture!(
+        #[derive(Clone, Copy)]
+        Started(i32),
+        |a, b| {
+            STARTED.fetch_add(1, Ordering::Relaxed);
+            thread::sleep(Duration::from_micros(200));
+            a * b
+        }
+    );
+
+    // records the most messages ever sent but not yet picked up by a worker
+    struct CountingSender<'a, T> {
+        inner: &'a mpsc::SyncSender<Msg<T>>,
+        sent: &'a AtomicUsize,
+        peak: &'a AtomicUsize,
+    }
+
+    impl<T> MsgSender<T> for CountingSender<'_, T> {
+        fn send_msg(&self, msg: Msg<T>) -> Result<(), mpsc::SendError<Msg<T>>> {
+            self.inner.send(msg)?;
+            let sent = self.sent.fetch_add(1, Ordering::Relaxed) + 1;
+            let in_flight = sent.saturating_sub(STARTED.load(Ordering::Relaxed));
+            self.peak.fetch_max(in_flight, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_multiply_bounded_caps_in_flight_messages() -> Result<()> {
+        let a = Matrix::new(vec![Started(1); 100], 100, 1);
+        let b = Matrix::new(vec![Started(1); 10], 1, 10);
+        let num_threads = 4;
+
+        for bound in [0, 1, 4] {
+            STARTED.store(0, Ordering::Relaxed);
+            let (sent, peak) = (AtomicUsize::new(0), AtomicUsize::new(0));
+            let workers = Workers::spawn_bounded(num_threads, bound);
+            let senders = workers
+                .senders
+                .iter()
+                .map(|inner| CountingSender {
+                    inner,
+                    sent: &sent,
+                    peak: &peak,
+                })
+                .collect::<Vec<_>>();
+
+            assert_eq!(dispatch(&a, &b, &senders)?.data, vec![Started(1); 1000]);
+            // each worker holds at most `bound` queued messages plus the one it took
+            assert!(peak.load(Ordering::Relaxed) <= num_threads * (bound + 1));
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_multiply_async() -> Result<()> {
+        let a = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        let b = Matrix::new(vec![1, 2, 3, 4, 5, 6], 3, 2);
+        assert_eq!(multiply_async(&a, &b).await?, multiply(&a, &b)?);
+        assert!(multiply_async(&a, &a).await.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_trace() -> Result<()> {
+        let m = Matrix::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 9], 3, 3);
+        assert_eq!(m.trace()?, 15);
+        assert_eq!(Matrix::<i32>::identity(4).trace()?, 4);
+
+        let m = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        assert_eq!(
+            m.trace(),
+            Err(ConcurrencyError::NotSquare { row: 2, col: 3 })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_hstack_and_vstack() -> Result<()> {
+        let a = Matrix::new(vec![1, 2, 3, 4], 2, 2);
+        let b = Matrix::new(vec![5, 6, 7, 8], 2, 2);
+        assert_eq!(
+            hstack(&a, &b)?,
+            Matrix::new(vec![1, 2, 5, 6, 3, 4, 7, 8], 2, 4)
+        );
+        assert_eq!(
+            vstack(&a, &b)?,
+            Matrix::new(vec![1, 2, 3, 4, 5, 6, 7, 8], 4, 2)
+        );
+
+        let c = Matrix::new(vec![1, 2, 3], 1, 3);
+        assert!(hstack(&a, &c).is_err());
+        assert!(vstack(&a, &c).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_worker_panic_returns_error() -> Result<()> {
+        let a = Matrix::new(vec![Bomb(1), Bomb(13), Bomb(3), Bomb(4)], 2, 2);
+        let b = Matrix::new(vec![Bomb(1), Bomb(2), Bomb(13), Bomb(4)], 2, 2);
+
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || tx.send(multiply_with_threads(&a, &b, 2)));
+        let ret = rx.recv_timeout(std::time::Duration::from_secs(5))?;
+        assert!(matches!(ret, Err(ConcurrencyError::Worker(_))));
+
+        // the pool survives a panicking cell
+        let multiplier = MatrixMultiplier::new(2)?;
+        let a = Matrix::new(vec![Bomb(13), Bomb(13)], 1, 2);
+        let b = Matrix::new(vec![Bomb(13), Bomb(13)], 2, 1);
+        assert!(multiplier.multiply(&a, &b).is_err());
+        let c = Matrix::new(vec![Bomb(2), Bomb(3)], 2, 1);
+        assert_eq!(
+            multiplier.multiply(&a, &c)?,
+            Matrix::new(vec![Bomb(65)], 1, 1)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_reshape() -> Result<()> {
+        let m = Matrix::new((1..=12).collect::<Vec<_>>(), 2, 6);
+        let r = m.reshape(4, 3)?;
+        assert_eq!(r.get(0, 2), Some(&3));
+        assert_eq!(r.get(1, 0), Some(&4));
+        assert_eq!(r.get(3, 2), Some(&12));
+        assert_eq!(r.reshape(2, 6)?, m);
+
+        assert_eq!(
+            m.reshape(5, 2),
+            Err(ConcurrencyError::LengthMismatch {
+                expected: 10,
+                got: 12
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_eq_distinguishes_dimensions() {
+        let row = Matrix::new(vec![1, 2, 3, 4], 1, 4);
+        let col = Matrix::new(vec![1, 2, 3, 4], 4, 1);
+        assert!(row != col);
+        assert_matrix_eq(&row, &Matrix::new(vec![1, 2, 3, 4], 1, 4));
+    }
+
+    #[test]
+    #[should_panic(expected = "matrices differ in shape: left is 1x4, right is 4x1")]
+    fn test_assert_matrix_eq_reports_shape() {
+        let row = Matrix::new(vec![1, 2, 3, 4], 1, 4);
+        let col = Matrix::new(vec![1, 2, 3, 4], 4, 1);
+        assert_matrix_eq(&row, &col);
+    }
+
+    #[test]
+    #[should_panic(expected = "matrices differ at (1, 0): left is 3, right is 5")]
+    fn test_assert_matrix_eq_reports_element() {
+        let a = Matrix::new(vec![1, 2, 3, 4], 2, 2);
+        let b = Matrix::new(vec![1, 2, 5, 4], 2, 2);
+        assert_matrix_eq(&a, &b);
+    }
+
+    #[test]
+    fn test_submatrix() -> Result<()> {
+        let m = Matrix::new((1..=16).collect::<Vec<_>>(), 4, 4);
+        assert_eq!(
+            m.submatrix(0..2, 2..4)?,
+            Matrix::new(vec![3, 4, 7, 8], 2, 2)
+        );
+        assert_eq!(m.submatrix(3..4, 0..1)?, Matrix::new(vec![13], 1, 1));
+
+        assert!(m.submatrix(2..5, 0..2).is_err());
+        assert!(m.submatrix(0..2, 3..5).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiply_strassen() -> Result<()> {
+        let n = 64;
+        let a = Matrix::new(
+            (0..n * n).map(|x| (x % 11) as i64 - 5).collect::<Vec<_>>(),
+            n,
+            n,
+        );
+        let b = Matrix::new(
+            (0..n * n).map(|x| (x % 7) as i64 - 3).collect::<Vec<_>>(),
+            n,
+            n,
+        );
+        assert_eq!(multiply_strassen(&a, &b)?, multiply(&a, &b)?);
+
+        // shapes strassen can't split fall back to multiply
+        let a = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        let b = Matrix::new(vec![1, 2, 3, 4, 5, 6], 3, 2);
+        assert_eq!(multiply_strassen(&a, &b)?, multiply(&a, &b)?);
+        assert!(multiply_strassen(&a, &a).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_determinant() -> Result<()> {
+        assert_eq!(Matrix::new(vec![7.0], 1, 1).determinant()?, 7.0);
+
+        let m = Matrix::new(vec![1.0, 2.0, 3.0, 4.0], 2, 2);
+        assert!((m.determinant()? - -2.0_f64).abs() < 1e-12);
+
+        let m = Matrix::new(vec![2.0, -3.0, 1.0, 2.0, 0.0, -1.0, 1.0, 4.0, 5.0], 3, 3);
+        assert!((m.determinant()? - 49.0_f64).abs() < 1e-9);
+
+        let singular = Matrix::new(vec![1.0, 2.0, 2.0, 4.0], 2, 2);
+        assert_eq!(singular.determinant()?, 0.0);
+
+        let m = Matrix::new(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 2, 3);
+        assert!(m.determinant().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_inverse() -> Result<()> {
+        let m = Matrix::new(vec![4.0, 7.0, 2.0, 6.0], 2, 2);
+        let inv = m.inverse()?;
+        assert!(inv.approx_eq(&Matrix::new(vec![0.6, -0.7, -0.2, 0.4], 2, 2), 1e-9));
+        assert!(multiply(&inv, &m)?.approx_eq(&Matrix::identity(2), 1e-9));
+        assert!(multiply(&m, &inv)?.approx_eq(&Matrix::identity(2), 1e-9));
+
+        let singular = Matrix::new(vec![1.0, 2.0, 2.0, 4.0], 2, 2);
+        assert_eq!(singular.inverse(), Err(ConcurrencyError::Singular));
+
+        let m = Matrix::new(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 2, 3);
+        assert!(m.inverse().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiply_into() -> Result<()> {
+        let a = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        let b = Matrix::new(vec![1, 2, 3, 4, 5, 6], 3, 2);
+        let mut out = Matrix::zeros(2, 2);
+        let ptr = out.data.as_ptr();
+
+        multiply_into(&a, &b, &mut out)?;
+        assert_eq!(out, Matrix::new(vec![22, 28, 49, 64], 2, 2));
+
+        let b = Matrix::new(vec![1, 0, 0, 1, 1, 1], 3, 2);
+        multiply_into(&a, &b, &mut out)?;
+        assert_eq!(out, Matrix::new(vec![4, 5, 10, 11], 2, 2));
+        assert_eq!(out.data.as_ptr(), ptr);
+
+        let multiplier = MatrixMultiplier::new(2)?;
+        multiplier.multiply_into(&a, &b, &mut out)?;
+        assert_eq!(out, Matrix::new(vec![4, 5, 10, 11], 2, 2));
+
+        let mut wrong = Matrix::zeros(3, 3);
+        assert!(multiply_into(&a, &b, &mut wrong).is_err());
+        assert!(multiplier.multiply_into(&a, &b, &mut wrong).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiply_ref_clone_type() -> Result<()> {
+        let a = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        let b = Matrix::new(vec![7, 8, 9, 10, 11, 12], 3, 2);
+        let expected = multiply(&a, &b)?;
+
+        let big = |m: &Matrix<i64>| {
+            Matrix::new(
+                m.data.iter().map(|&v| Big(v)).collect::<Vec<_>>(),
+                m.row,
+                m.col,
+            )
+        };
+        let c = multiply_ref(&big(&a), &big(&b))?;
+        assert_eq!(c, big(&expected));
+        assert_eq!(multiply_ref(&a, &b)?, expected);
+
+        assert!(multiply_ref(&big(&a), &big(&a)).is_err());
+
+        // every worker panics, not just the first one joined
+        let bombs = Matrix::new(vec![Bomb(13); 64], 8, 8);
+        assert!(matches!(
+            multiply_ref(&bombs, &bombs),
+            Err(ConcurrencyError::Worker(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_vec_of_rows_round_trip() -> Result<()> {
+        let m = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        let rows = m.to_vec_of_rows();
+        assert_eq!(rows, vec![vec![1, 2, 3], vec![4, 5, 6]]);
+        assert_eq!(Matrix::from_rows(rows)?, m);
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiply_checked() -> Result<()> {
+        let a = Matrix::new(vec![1, 2, 3, 4], 2, 2);
+        assert_eq!(multiply_checked(&a, &a)?, multiply(&a, &a)?);
+
+        let big = Matrix::new(vec![i32::MAX / 2, i32::MAX / 2, 1, 1], 2, 2);
+        let b = Matrix::new(vec![2, 0, 2, 0], 2, 2);
+        assert_eq!(multiply_checked(&big, &b), Err(ConcurrencyError::Overflow));
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiply_cancellable() -> Result<()> {
+        let a = Matrix::new(vec![1, 2, 3, 4], 2, 2);
+        let cancel = Arc::new(AtomicBool::new(false));
+        assert_eq!(
+            multiply_cancellable(&a, &a, cancel.clone())?,
+            multiply(&a, &a)?
+        );
+
+        cancel.store(true, Ordering::Relaxed);
+        assert_eq!(
+            multiply_cancellable(&a, &a, cancel),
+            Err(ConcurrencyError::Cancelled)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiply_cancellable_midway() {
+        let n = 400;
+        let a = Matrix::new(vec![1i64; n * n], n, n);
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = cancel.clone();
+        let canceller = thread::spawn(move || {
+            thread::sleep(std::time::Duration::from_millis(5));
+            flag.store(true, Ordering::Relaxed);
+        });
+
+        let start = std::time::Instant::now();
+        let result = multiply_cancellable(&a, &a, cancel);
+        canceller.join().unwrap();
+        assert_eq!(result, Err(ConcurrencyError::Cancelled));
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_format_aligned() {
+        let m = Matrix::new(vec![1.0, -22.5, 333.25, 4.0], 2, 2);
+        assert_eq!(m.format_aligned(2), "  1.00 -22.50\n333.25   4.00");
+        assert_eq!(m.format_aligned(0), "  1 -22\n333   4");
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    #[test]
+    fn test_multiply_sync_only_required_with_rayon() {
+        fn bound<T: MultiplySync>() {}
+        bound::<std::cell::Cell<i32>>();
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_multiply_rayon_matches_channels() -> Result<()> {
+        let a = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        let b = Matrix::new(vec![1, 2, 3, 4, 5, 6], 3, 2);
+        assert_eq!(multiply(&a, &b)?, multiply_with_threads(&a, &b, 4)?);
+        assert_eq!(multiply(&a, &b)?, Matrix::new(vec![22, 28, 49, 64], 2, 2));
+        assert!(multiply(&a, &a).is_err());
+
+        let bombs = Matrix::new(vec![Bomb(13); 64], 8, 8);
+        assert!(matches!(
+            multiply(&bombs, &bombs),
+            Err(ConcurrencyError::Worker(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_diagonal() -> Result<()> {
+        let m = Matrix::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 9], 3, 3);
+        assert_eq!(*m.diagonal()?, vec![1, 5, 9]);
+        assert_eq!(*Matrix::<i32>::identity(3).diagonal()?, vec![1, 1, 1]);
+        assert!(Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3)
+            .diagonal()
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiply_with_stats() -> Result<()> {
+        let a = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        let b = Matrix::new(vec![1, 2, 3, 4, 5, 6], 3, 2);
+        let (c, stats) = multiply_with_stats(&a, &b)?;
+        assert_eq!(c, multiply(&a, &b)?);
+        assert_eq!(stats.messages, a.row * b.col);
+        assert!(stats.threads > 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_square_and_symmetric() {
+        let identity = Matrix::<i32>::identity(3);
+        assert!(identity.is_square() && identity.is_symmetric());
+
+        let symmetric = Matrix::new(vec![1, 2, 3, 2, 4, 5, 3, 5, 6], 3, 3);
+        assert!(symmetric.is_symmetric());
+
+        let square = Matrix::new(vec![1, 2, 3, 4], 2, 2);
+        assert!(square.is_square());
+        assert!(!square.is_symmetric());
+
+        let wide = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        assert!(!wide.is_square());
+        assert!(!wide.is_symmetric());
+    }
+
+    #[test]
+    fn test_multiply_owned() -> Result<()> {
+        let a = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        let b = Matrix::new(vec![1, 2, 3, 4, 5, 6], 3, 2);
+        let expected = multiply(&a, &b)?;
+
+        let ptr = a.data.as_ptr();
+        let c = multiply_owned(a, b.clone())?;
+        assert_eq!(c, expected);
+        // 2x2 fits in the 2x3 buffer
+        assert_eq!(c.data.as_ptr(), ptr);
+
+        assert!(multiply_owned(b.clone(), b).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_swap_rows_and_cols() -> Result<()> {
+        let original = Matrix::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 9], 3, 3);
+        let mut m = original.clone();
+        m.swap_rows(0, 2)?;
+        assert_eq!(m, Matrix::new(vec![7, 8, 9, 4, 5, 6, 1, 2, 3], 3, 3));
+        m.swap_rows(2, 0)?;
+        assert_eq!(m, original);
+
+        m.swap_cols(0, 1)?;
+        assert_eq!(m, Matrix::new(vec![2, 1, 3, 5, 4, 6, 8, 7, 9], 3, 3));
+        m.swap_cols(0, 1)?;
+        m.swap_rows(1, 1)?;
+        assert_eq!(m, original);
+
+        assert!(m.swap_rows(0, 3).is_err());
+        assert!(m.swap_cols(3, 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_csv() -> Result<()> {
+        let m = Matrix::from_csv("1,2,3\n 4, 5 ,6\n")?;
+        assert_eq!(m, Matrix::new(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 2, 3));
+        assert_eq!(Matrix::try_from("1,2,3\n4,5,6")?, m);
+
+        assert_eq!(
+            Matrix::from_csv("1,2,3\n4,5"),
+            Err(ConcurrencyError::LengthMismatch {
+                expected: 3,
+                got: 2
+            })
+        );
+        assert!(matches!(
+            Matrix::from_csv("1,2\n3,x"),
+            Err(ConcurrencyError::Parse(_))
+        ));
+        // blank lines still count towards the reported line
+        assert_eq!(
+            Matrix::from_csv("1,2\n\n3,x"),
+            Err(ConcurrencyError::Parse(
+                "line 3: \"x\": invalid float literal".to_string()
+            ))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_csv() -> Result<()> {
+        let m = Matrix::new(vec![1.5, 2.0, -3.25, 4.0], 2, 2);
+        assert_eq!(m.to_csv(), "1.5,2\n-3.25,4");
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_round_trip() -> Result<()> {
+        let m = Matrix::new(vec![0.1, 1.0 / 3.0, -2.5e-8, 1e300, 7.0, 0.0], 2, 3);
+        assert_eq!(Matrix::from_csv(&m.to_csv())?, m);
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiply_as_widens() -> Result<()> {
+        let a = Matrix::new(vec![100i8, 100, 100, 100], 2, 2);
+        // 100 * 100 + 100 * 100 doesn't fit in an i8
+        let c: Matrix<i32> = multiply_as(&a, &a)?;
+        assert_eq!(c, Matrix::new(vec![20000, 20000, 20000, 20000], 2, 2));
+        assert!(multiply_as::<i8, i32>(&a, &Matrix::new(vec![1i8; 3], 3, 1)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_par_map_matches_map() {
+        let m = Matrix::new((0..100).map(|x| x as f64).collect::<Vec<_>>(), 10, 10);
+        let f = |v: &f64| (v / 10.0).exp();
+        assert_eq!(m.par_map(f), m.map(f));
+
+        let empty = Matrix::<i32>::new(vec![], 0, 0);
+        assert_eq!(empty.par_map(|v| v + 1), empty);
+    }
+
+    #[test]
+    fn test_multiply_with_timeout() -> Result<()> {
+        let a = Matrix::new(vec![1, 2, 3, 4], 2, 2);
+        assert_eq!(
+            multiply_with_timeout(&a, &a, Duration::from_secs(5))?,
+            multiply(&a, &a)?
+        );
+
+        let column = Matrix::new(vec![Slow(1), Slow(2)], 2, 1);
+        let row = Matrix::new(vec![Slow(3), Slow(4)], 1, 2);
+        let ret = multiply_with_timeout(&column, &row, Duration::from_millis(10));
+        assert!(matches!(ret, Err(ConcurrencyError::Timeout(_))));
+        // every worker has been joined, so none is still inside a product
+        assert_eq!(SLOW_RUNNING.load(Ordering::Relaxed), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_workers_skip_queued_cells_after_error() -> Result<()> {
+        // cell 0 fails straight away; the other 399 cells are 20 products each
+        let mut a = Matrix::new(vec![Tick(1); 400], 20, 20);
+        a.data[0] = Tick(13);
+        let b = a.clone();
+
+        assert!(matches!(
+            multiply_with_threads(&a, &b, 4),
+            Err(ConcurrencyError::Worker(_))
+        ));
+        // at most the cells the workers were already on get finished
+        assert!(TICKS.load(Ordering::Relaxed) < 400 * 20 / 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_comparison_masks() {
+        let m = Matrix::new(vec![1, 5, 3, 2], 2, 2);
+        assert_eq!(m.gt(2), Matrix::new(vec![false, true, true, false], 2, 2));
+        assert_eq!(m.lt(2), Matrix::new(vec![true, false, false, false], 2, 2));
+        assert_eq!(m.ge(2), Matrix::new(vec![false, true, true, true], 2, 2));
+        assert_eq!(m.le(2), Matrix::new(vec![true, false, false, true], 2, 2));
+    }
+
+    #[test]
+    fn test_select() -> Result<()> {
+        let a = Matrix::new(vec![1, 2, 3, 4], 2, 2);
+        let b = Matrix::new(vec![10, 20, 30, 40], 2, 2);
+        let mask = Matrix::new(vec![true, false, false, true], 2, 2);
+        assert_eq!(
+            Matrix::select(&mask, &a, &b)?,
+            Matrix::new(vec![1, 20, 30, 4], 2, 2)
+        );
+        assert_eq!(
+            Matrix::select(&a.gt(2), &a, &b)?,
+            Matrix::new(vec![10, 20, 3, 4], 2, 2)
+        );
+
+        let wide = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        assert!(Matrix::select(&mask, &a, &wide).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "tracing")]
+    mod tracing_events {
+        use super::*;
+        use std::sync::{
+            atomic::{AtomicU64, Ordering},
+            Mutex,
+        };
+        use tracing::{
+            field::{Field, Visit},
+            span, Event, Metadata, Subscriber,
+        };
+
+        // records span names and each event's fields as strings
+        #[derive(Default)]
+        struct Recorder {
+            next_id: AtomicU64,
+            spans: Mutex<Vec<String>>,
+            events: Mutex<Vec<Vec<(String, String)>>>,
+        }
+
+        struct Fields(Vec<(String, String)>);
+
+        impl Visit for Fields {
+            fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+                self.0
+                    .push((field.name().to_string(), format!("{:?}", value)));
+            }
+        }
+
+        impl Subscriber for &'static Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, attrs: &span::Attributes<'_>) -> span::Id {
+                self.spans
+                    .lock()
+                    .unwrap()
+                    .push(attrs.metadata().name().to_string());
+                span::Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+            }
+
+            fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+            fn event(&self, event: &Event<'_>) {
+                let mut fields = Fields(Vec::new());
+                event.record(&mut fields);
+                self.events.lock().unwrap().push(fields.0);
+            }
+
+            fn enter(&self, _: &span::Id) {}
+
+            fn exit(&self, _: &span::Id) {}
+        }
+
+        #[test]
+        fn test_multiply_emits_events() -> Result<()> {
+            let recorder: &'static Recorder = Box::leak(Box::default());
+            let a = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+            let b = Matrix::new(vec![1, 2, 3, 4, 5, 6], 3, 2);
+            let c =
+                tracing::subscriber::with_default(recorder, || multiply_with_threads(&a, &b, 2))?;
+            assert_eq!(c, Matrix::new(vec![22, 28, 49, 64], 2, 2));
+
+            assert!(recorder
+                .spans
+                .lock()
+                .unwrap()
+                .contains(&"multiply".to_string()));
+            let events = recorder.events.lock().unwrap();
+            let finished = events
+                .iter()
+                .find(|fields| fields.iter().any(|(_, v)| v == "multiply finished"))
+                .ok_or_else(|| anyhow::anyhow!("no multiply finished event"))?;
+            let field = |name: &str| {
+                finished
+                    .iter()
+                    .find(|(k, _)| k == name)
+                    .map(|(_, v)| v.as_str())
+            };
+            assert_eq!(field("workers"), Some("2"));
+            assert_eq!(field("messages"), Some("4"));
+            assert!(field("elapsed_us").is_some());
+            Ok(())
+        }
+
+        #[test]
+        fn test_multiply_emits_finished_on_every_path() -> Result<()> {
+            let recorder: &'static Recorder = Box::leak(Box::default());
+            let a = Matrix::new(vec![1, 2, 3, 4], 2, 2);
+            let bombs = Matrix::new(vec![Bomb(13); 4], 2, 2);
+            tracing::subscriber::with_default(recorder, || -> Result<()> {
+                assert_eq!(
+                    multiply_with_threads(&a, &a, 1)?,
+                    multiply_sequential(&a, &a)?
+                );
+                assert!(multiply_with_threads(&bombs, &bombs, 2).is_err());
+                Ok(())
+            })?;
+
+            let events = recorder.events.lock().unwrap();
+            let finished = events
+                .iter()
+                .filter(|fields| fields.iter().any(|(_, v)| v == "multiply finished"))
+                .map(|fields| {
+                    fields
+                        .iter()
+                        .find(|(k, _)| k == "workers")
+                        .map(|(_, v)| v.clone())
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(finished, vec![Some("1".to_string()), Some("2".to_string())]);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_kron() {
+        let a = Matrix::new(vec![1, 2, 3, 4], 2, 2);
+        let b = Matrix::new(vec![0, 5, 6, 7], 2, 2);
+        let expected = Matrix::new(
+            // [[0,5,0,10]
+            // [6,7,12,14]
+            // [0,15,0,20]
+            // [18,21,24,28]]
+            vec![0, 5, 0, 10, 6, 7, 12, 14, 0, 15, 0, 20, 18, 21, 24, 28],
+            4,
+            4,
+        );
+        assert_eq!(kron(&a, &b), expected);
+
+        let row = Matrix::new(vec![1, 2], 1, 2);
+        let col = Matrix::new(vec![1, 10], 2, 1);
+        assert_eq!(kron(&row, &col), Matrix::new(vec![1, 2, 10, 20], 2, 2));
+    }
+
+    #[test]
+    fn test_resize() {
+        let m = Matrix::new(vec![1, 2, 3, 4], 2, 2);
+        assert_eq!(
+            m.resize(3, 3, 0),
+            Matrix::new(vec![1, 2, 0, 3, 4, 0, 0, 0, 0], 3, 3)
+        );
+
+        let m = Matrix::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 9], 3, 3);
+        assert_eq!(m.resize(2, 2, 0), Matrix::new(vec![1, 2, 4, 5], 2, 2));
+        assert_eq!(
+            m.resize(2, 4, -1),
+            Matrix::new(vec![1, 2, 3, -1, 4, 5, 6, -1], 2, 4)
+        );
+    }
+
+    #[test]
+    fn test_apply_row_and_col() -> Result<()> {
+        let mut m = Matrix::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 9], 3, 3);
+        m.apply_row(1, |v| *v *= 2)?;
+        assert_eq!(m, Matrix::new(vec![1, 2, 3, 8, 10, 12, 7, 8, 9], 3, 3));
+
+        m.apply_col(2, |v| *v *= 2)?;
+        assert_eq!(m, Matrix::new(vec![1, 2, 6, 8, 10, 24, 7, 8, 18], 3, 3));
+
+        assert!(m.apply_row(3, |v| *v = 0).is_err());
+        assert!(m.apply_col(3, |v| *v = 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_as_slice_and_dimensions() {
+        let m = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        let (row, col) = m.dimensions();
+        assert_eq!((row, col), (2, 3));
+        assert_eq!(m.as_slice().len(), row * col);
+
+        let displayed = m.to_string();
+        let elements = displayed
+            .trim_matches(|c| c == '{' || c == '}')
+            .split([' ', ','])
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse::<i32>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(m.as_slice(), elements.as_slice());
+    }
+
+    #[test]
+    fn test_has_nan() {
+        assert!(!Matrix::new(vec![1.0, 2.0, 3.0, 4.0], 2, 2).has_nan());
+        assert!(Matrix::new(vec![1.0, f64::NAN, 3.0, 4.0], 2, 2).has_nan());
+        assert!(!Matrix::new(vec![1.0, f64::INFINITY, 3.0, 4.0], 2, 2).has_nan());
+    }
+
+    #[test]
+    fn test_argmax() {
+        assert_eq!(Matrix::new(vec![1, 3, 2, 0], 2, 2).argmax(), Some((0, 1)));
+        assert_eq!(
+            Matrix::new(vec![1, 2, 5, 0, 5, 5], 2, 3).argmax(),
+            Some((0, 2))
+        );
+        assert_eq!(Matrix::<i32>::new(vec![], 0, 0).argmax(), None);
+    }
+
+    #[test]
+    fn test_dispatch_skips_dead_worker() -> Result<()> {
+        let a = Matrix::new(vec![1, 2, 3, 4], 2, 2);
+
+        let (dead, rx) = mpsc::channel::<Msg<i32>>();
+        drop(rx);
+        let (live, rx) = mpsc::channel::<Msg<i32>>();
+        spawn_worker(rx, Arc::default());
+        assert_eq!(dispatch(&a, &a, &[dead, live])?, multiply(&a, &a)?);
+
+        let (dead, rx) = mpsc::channel::<Msg<i32>>();
+        drop(rx);
+        assert_eq!(
+            dispatch(&a, &a, &[dead]),
+            Err(ConcurrencyError::Worker(
+                "all 1 workers are gone, cell 0 could not be dispatched".to_string()
+            ))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_flip() {
+        let m = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        assert_eq!(
+            m.flip_horizontal(),
+            Matrix::new(vec![3, 2, 1, 6, 5, 4], 2, 3)
+        );
+        assert_eq!(m.flip_vertical(), Matrix::new(vec![4, 5, 6, 1, 2, 3], 2, 3));
+        assert_eq!(m.flip_horizontal().flip_horizontal(), m);
+    }
+
+    #[test]
+    fn test_rotate90() {
+        let m = Matrix::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        // [[1,2,3]    [[4,1]
+        //  [4,5,6]] -> [5,2]
+        //              [6,3]]
+        assert_eq!(m.rotate90(), Matrix::new(vec![4, 1, 5, 2, 6, 3], 3, 2));
+        assert_eq!(m.rotate90(), m.transpose().flip_horizontal());
+        assert_eq!(m.rotate90().rotate90().rotate90().rotate90(), m);
+    }
+
+    #[test]
+    fn test_lu() -> Result<()> {
+        let a = Matrix::new(vec![2.0, 1.0, 1.0, 4.0, -6.0, 0.0, -2.0, 7.0, 2.0], 3, 3);
+        let (l, u, perm) = a.lu()?;
+        for i in 0..3 {
+            assert_eq!(l.data[i * 3 + i], 1.0);
+            for j in i + 1..3 {
+                assert_eq!(l.data[i * 3 + j], 0.0);
+                assert_eq!(u.data[j * 3 + i], 0.0);
+            }
+        }
+
+        let pa = Matrix::from_rows(
+            perm.iter()
+                .map(|&i| a.rows().nth(i).unwrap().to_vec())
+                .collect(),
+        )?;
+        assert!(multiply(&l, &u)?.approx_eq(&pa, 1e-9));
+
+        let singular = Matrix::new(vec![1.0, 2.0, 3.0, 2.0, 4.0, 6.0, 1.0, 0.0, 1.0], 3, 3);
+        assert_eq!(singular.lu(), Err(ConcurrencyError::Singular));
+        assert!(Matrix::new(vec![1.0, 2.0], 1, 2).lu().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_solve() -> Result<()> {
+        // 2x + y - z = 8, -3x - y + 2z = -11, -2x + y + 2z = -3 has x = (2, 3, -1)
+        let a = Matrix::new(vec![2.0, 1.0, -1.0, -3.0, -1.0, 2.0, -2.0, 1.0, 2.0], 3, 3);
+        let b = Vector::new([8.0, -11.0, -3.0]);
+        let x = solve(&a, &b)?;
+        for (got, expected) in x.iter().zip([2.0, 3.0, -1.0]) {
+            assert!((got - expected).abs() < 1e-9);
+        }
+
+        let ax = a.mul_vector(&x)?;
+        for (got, expected) in ax.iter().zip(b.iter()) {
+            assert!((got - expected).abs() < 1e-9);
+        }
+
+        assert!(solve(&a, &Vector::new([1.0, 2.0])).is_err());
+        assert!(solve(&Matrix::new(vec![1.0, 2.0], 1, 2), &Vector::new([1.0])).is_err());
+        let singular = Matrix::new(vec![1.0, 2.0, 2.0, 4.0], 2, 2);
+        assert!(matches!(
+            solve(&singular, &Vector::new([1.0, 2.0])),
+            Err(ConcurrencyError::Singular)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_approx_eq_nan_equal() {
+        let a = Matrix::new(vec![1.0, f64::NAN, f64::INFINITY, 4.0], 2, 2);
+        let b = Matrix::new(vec![1.0 + 1e-12, f64::NAN, f64::INFINITY, 4.0], 2, 2);
+        assert!(a.approx_eq_nan_equal(&b, 1e-9));
+        assert!(!a.approx_eq(&b, 1e-9));
+
+        let c = Matrix::new(vec![f64::NAN, 2.0, f64::INFINITY, 4.0], 2, 2);
+        assert!(!a.approx_eq_nan_equal(&c, 1e-9));
     }
 }
@@ -1,52 +1,359 @@
-use anyhow::Result;
 use core::fmt;
 use std::sync::atomic::Ordering;
 use std::{
     collections::HashMap,
     sync::{atomic::AtomicI64, Arc},
+    time::Duration,
 };
 
+use super::MetricKind;
+use crate::error::{ConcurrencyError, Result};
+
 pub struct AmapMetrics {
-    data: Arc<HashMap<&'static str, AtomicI64>>,
+    data: Arc<HashMap<String, Metric>>,
+    ordering: Ordering,
+}
+
+struct Metric {
+    kind: MetricKind,
+    value: AtomicI64,
 }
 
 impl Clone for AmapMetrics {
     fn clone(&self) -> Self {
         AmapMetrics {
             data: Arc::clone(&self.data),
+            ordering: self.ordering,
         }
     }
 }
 
 impl AmapMetrics {
-    pub fn new(metric_names: &[&'static str]) -> Self {
-        let map = metric_names
+    pub fn new(metric_names: &[&str]) -> Self {
+        Self::with_gauges(metric_names, &[])
+    }
+
+    pub fn with_gauges(counter_names: &[&str], gauge_names: &[&str]) -> Self {
+        let counters = counter_names
+            .iter()
+            .map(|&name| (name.to_string(), MetricKind::Counter));
+        let gauges = gauge_names
             .iter()
-            .map(|&name| (name, AtomicI64::new(0)))
+            .map(|&name| (name.to_string(), MetricKind::Gauge));
+        Self::from_names(counters.chain(gauges))
+    }
+
+    /// Counters named at runtime, e.g. from config.
+    pub fn with_owned_names(names: Vec<String>) -> Self {
+        Self::from_names(names.into_iter().map(|name| (name, MetricKind::Counter)))
+    }
+
+    fn from_names(names: impl Iterator<Item = (String, MetricKind)>) -> Self {
+        let map = names
+            .map(|(name, kind)| {
+                let metric = Metric {
+                    kind,
+                    value: AtomicI64::new(0),
+                };
+                (name, metric)
+            })
             .collect();
         AmapMetrics {
             data: Arc::new(map),
+            ordering: Ordering::Relaxed,
+        }
+    }
+
+    /// Sets the memory ordering used for updates and reads. The default,
+    /// `Relaxed`, is the cheapest and enough when each counter is only looked at
+    /// on its own. Use `Release`/`Acquire` (or `SeqCst`) when a reader must also
+    /// see the writes a thread made before updating a metric; `SeqCst` adds a
+    /// single total order across all metrics at the highest cost.
+    pub fn with_ordering(mut self, ordering: Ordering) -> Self {
+        self.ordering = ordering;
+        self
+    }
+
+    // loads can't be Release and stores can't be Acquire, so map the configured
+    // ordering onto the strongest one each operation accepts
+    fn load_ordering(&self) -> Ordering {
+        match self.ordering {
+            Ordering::Relaxed => Ordering::Relaxed,
+            Ordering::SeqCst => Ordering::SeqCst,
+            _ => Ordering::Acquire,
+        }
+    }
+
+    fn store_ordering(&self) -> Ordering {
+        match self.ordering {
+            Ordering::Relaxed => Ordering::Relaxed,
+            Ordering::SeqCst => Ordering::SeqCst,
+            _ => Ordering::Release,
         }
     }
 
     pub fn inc(&self, key: impl AsRef<str>) -> Result<()> {
+        self.add(key, 1)
+    }
+
+    pub fn dec(&self, key: impl AsRef<str>) -> Result<()> {
+        let metric = self.metric(key.as_ref())?;
+        metric.value.fetch_sub(1, self.ordering);
+        Ok(())
+    }
+
+    pub fn add(&self, key: impl AsRef<str>, delta: i64) -> Result<()> {
+        let metric = self.metric(key.as_ref())?;
+        metric.value.fetch_add(delta, self.ordering);
+        Ok(())
+    }
+
+    pub fn set(&self, key: impl AsRef<str>, value: i64) -> Result<()> {
         let key = key.as_ref();
-        let counter = self
+        let metric = self.metric(key)?;
+        if metric.kind != MetricKind::Gauge {
+            return Err(ConcurrencyError::KindMismatch {
+                key: key.to_string(),
+                kind: metric.kind,
+            });
+        }
+        metric.value.store(value, self.store_ordering());
+        Ok(())
+    }
+
+    pub fn snapshot(&self) -> HashMap<String, i64> {
+        self.data
+            .iter()
+            .map(|(key, metric)| (key.clone(), metric.value.load(self.load_ordering())))
+            .collect()
+    }
+
+    /// Per-second change of every metric since `prev`, a snapshot taken `elapsed`
+    /// ago. Keys missing from `prev` count from zero, and a counter that went down
+    /// (e.g. it was reset) reports a rate of 0 rather than a negative one.
+    pub fn rate_since(
+        &self,
+        prev: &HashMap<String, i64>,
+        elapsed: Duration,
+    ) -> Result<HashMap<String, f64>> {
+        let secs = elapsed.as_secs_f64();
+        if secs == 0.0 {
+            return Err(ConcurrencyError::InvalidArgument(
+                "elapsed must be greater than 0".to_string(),
+            ));
+        }
+
+        let rates = self
             .data
-            .get(key)
-            .ok_or_else(|| anyhow::anyhow!("key: {} not found", key))?;
+            .iter()
+            .map(|(key, metric)| {
+                let delta =
+                    metric.value.load(self.load_ordering()) - prev.get(key).copied().unwrap_or(0);
+                let delta = match metric.kind {
+                    MetricKind::Counter => delta.max(0),
+                    MetricKind::Gauge => delta,
+                };
+                (key.clone(), delta as f64 / secs)
+            })
+            .collect();
+        Ok(rates)
+    }
 
-        counter.fetch_add(1, Ordering::Relaxed);
+    pub fn to_prometheus(&self) -> String {
+        let entries = self
+            .data
+            .iter()
+            .map(|(key, metric)| (key, metric.value.load(self.load_ordering()), metric.kind))
+            .collect();
+        super::to_prometheus(entries)
+    }
 
-        Ok(())
+    fn metric(&self, key: &str) -> Result<&Metric> {
+        self.data
+            .get(key)
+            .ok_or_else(|| ConcurrencyError::KeyNotFound(key.to_string()))
     }
 }
 
 impl fmt::Display for AmapMetrics {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for (key, value) in self.data.iter() {
-            writeln!(f, "{}: {}", key, value.load(Ordering::Relaxed))?;
+        let mut entries = self.data.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|&(key, _)| key);
+        for (key, metric) in entries {
+            let value = metric.value.load(self.load_ordering());
+            match metric.kind {
+                MetricKind::Counter => writeln!(f, "{}: {}", key, value)?,
+                MetricKind::Gauge => writeln!(f, "{}: {} (gauge)", key, value)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for AmapMetrics {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_map(self.snapshot())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_inc_dec_add() -> Result<()> {
+        let metrics = AmapMetrics::new(&["inflight"]);
+        metrics.inc("inflight")?;
+        metrics.inc("inflight")?;
+        metrics.dec("inflight")?;
+        metrics.dec("inflight")?;
+        assert_eq!(metrics.snapshot()["inflight"], 0);
+
+        metrics.add("inflight", 5)?;
+        assert_eq!(metrics.snapshot()["inflight"], 5);
+
+        assert_eq!(
+            metrics.dec("unknown"),
+            Err(ConcurrencyError::KeyNotFound("unknown".to_string()))
+        );
+        assert!(metrics.add("unknown", 5).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot() -> Result<()> {
+        let metrics = AmapMetrics::new(&["requests", "errors", "idle"]);
+        for _ in 0..3 {
+            metrics.inc("requests")?;
+        }
+        metrics.inc("errors")?;
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.len(), 3);
+        assert_eq!(snapshot["requests"], 3);
+        assert_eq!(snapshot["errors"], 1);
+        assert_eq!(snapshot["idle"], 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_display_is_sorted() -> Result<()> {
+        let metrics = AmapMetrics::new(&["b", "a", "c"]);
+        metrics.inc("a")?;
+        metrics.add("c", 3)?;
+        assert_eq!(format!("{}", metrics), "a: 1\nb: 0\nc: 3\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_prometheus() -> Result<()> {
+        let metrics = AmapMetrics::new(&["http_requests", "req.page.1"]);
+        metrics.add("http_requests", 42)?;
+        metrics.inc("req.page.1")?;
+        assert_eq!(
+            metrics.to_prometheus(),
+            "# TYPE http_requests counter\nhttp_requests 42\n# TYPE req_page_1 counter\nreq_page_1 1\n"
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() -> Result<()> {
+        let metrics = AmapMetrics::new(&["a", "b"]);
+        metrics.inc("a")?;
+        metrics.add("b", 2)?;
+
+        let json = serde_json::to_string(&metrics)?;
+        let value: serde_json::Value = serde_json::from_str(&json)?;
+        assert_eq!(value, serde_json::json!({"a": 1, "b": 2}));
+        Ok(())
+    }
+
+    #[test]
+    fn test_gauge_set() -> Result<()> {
+        let metrics = AmapMetrics::with_gauges(&["requests"], &["queue.depth"]);
+        metrics.set("queue.depth", 10)?;
+        metrics.set("queue.depth", 3)?;
+        assert_eq!(metrics.snapshot()["queue.depth"], 3);
+
+        assert_eq!(
+            metrics.set("requests", 1),
+            Err(ConcurrencyError::KindMismatch {
+                key: "requests".to_string(),
+                kind: MetricKind::Counter
+            })
+        );
+        assert_eq!(
+            format!("{}", metrics),
+            "queue.depth: 3 (gauge)\nrequests: 0\n"
+        );
+        assert_eq!(
+            metrics.to_prometheus(),
+            "# TYPE queue_depth gauge\nqueue_depth 3\n# TYPE requests counter\nrequests 0\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_rate_since() -> Result<()> {
+        let metrics = AmapMetrics::with_gauges(&["requests", "resets"], &["queue.depth"]);
+        metrics.add("requests", 30)?;
+        metrics.add("resets", 1)?;
+        metrics.set("queue.depth", 2)?;
+
+        let prev = HashMap::from([
+            ("requests".to_string(), 10),
+            ("resets".to_string(), 5),
+            ("queue.depth".to_string(), 8),
+        ]);
+        let rates = metrics.rate_since(&prev, Duration::from_secs(4))?;
+        assert_eq!(rates["requests"], 5.0);
+        assert_eq!(rates["resets"], 0.0);
+        assert_eq!(rates["queue.depth"], -1.5);
+
+        assert!(metrics.rate_since(&prev, Duration::ZERO).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_ordering() -> Result<()> {
+        let metrics =
+            AmapMetrics::with_gauges(&["requests"], &["ready"]).with_ordering(Ordering::SeqCst);
+        metrics.inc("requests")?;
+        metrics.set("ready", 1)?;
+        assert_eq!(metrics.snapshot()["requests"], 1);
+
+        let writer = metrics.clone().with_ordering(Ordering::Release);
+        let reader = metrics.clone().with_ordering(Ordering::Acquire);
+        let handle = std::thread::spawn(move || writer.add("requests", 41));
+        handle
+            .join()
+            .map_err(|e| anyhow::anyhow!("Thread join error {:?}", e))??;
+        assert_eq!(reader.snapshot()["requests"], 42);
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_owned_names() -> Result<()> {
+        let names = (0..3).map(|i| format!("shard.{}", i)).collect::<Vec<_>>();
+        let metrics = AmapMetrics::with_owned_names(names);
+        for i in 0..3 {
+            for _ in 0..=i {
+                metrics.inc(format!("shard.{}", i))?;
+            }
         }
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.len(), 3);
+        assert_eq!(snapshot["shard.0"], 1);
+        assert_eq!(snapshot["shard.2"], 3);
+        assert!(metrics.inc("shard.3").is_err());
         Ok(())
     }
 }
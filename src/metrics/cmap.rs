@@ -1,8 +1,13 @@
-use anyhow::Result;
 use core::fmt;
 use dashmap::DashMap;
-use std::{fmt::Debug, sync::Arc};
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
 
+use super::MetricKind;
+use crate::error::Result;
+
+/// Counters created on first use. The map is a `DashMap`, which shards its keys
+/// over independently locked buckets, so increments on different keys rarely
+/// contend with each other.
 #[derive(Debug, Clone)]
 pub struct CmapMetrics {
     data: Arc<DashMap<String, i64>>,
@@ -26,6 +31,22 @@ impl CmapMetrics {
         *counter -= 1;
         Ok(())
     }
+
+    pub fn snapshot(&self) -> HashMap<String, i64> {
+        self.data
+            .iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect()
+    }
+
+    pub fn to_prometheus(&self) -> String {
+        let entries = self
+            .snapshot()
+            .into_iter()
+            .map(|(key, value)| (key, value, MetricKind::Counter))
+            .collect();
+        super::to_prometheus(entries)
+    }
 }
 
 impl Default for CmapMetrics {
@@ -36,9 +57,113 @@ impl Default for CmapMetrics {
 
 impl fmt::Display for CmapMetrics {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for entry in self.data.iter() {
-            writeln!(f, "{}: {}", entry.key(), entry.value())?;
+        let mut entries = self.snapshot().into_iter().collect::<Vec<_>>();
+        entries.sort();
+        for (key, value) in entries {
+            writeln!(f, "{}: {}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for CmapMetrics {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_map(self.snapshot())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    use std::thread;
+
+    #[test]
+    fn test_inc_new_key_from_threads() -> Result<()> {
+        let metrics = CmapMetrics::new();
+        let handles = (0..8)
+            .map(|_| {
+                let metrics = metrics.clone();
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        metrics.inc("req.page.1")?;
+                    }
+                    Ok::<_, anyhow::Error>(())
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle
+                .join()
+                .map_err(|e| anyhow::anyhow!("Thread join error {:?}", e))??;
         }
+
+        assert_eq!(metrics.snapshot()["req.page.1"], 8000);
+        Ok(())
+    }
+
+    #[test]
+    fn test_display_matches_amap() -> Result<()> {
+        let metrics = CmapMetrics::new();
+        metrics.inc("b")?;
+        metrics.inc("a")?;
+        metrics.dec("c")?;
+        assert_eq!(format!("{}", metrics), "a: 1\nb: 1\nc: -1\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_prometheus() -> Result<()> {
+        let metrics = CmapMetrics::new();
+        metrics.inc("call.thread.worker.0")?;
+        assert_eq!(
+            metrics.to_prometheus(),
+            "# TYPE call_thread_worker_0 counter\ncall_thread_worker_0 1\n"
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() -> Result<()> {
+        let metrics = CmapMetrics::new();
+        metrics.inc("a")?;
+        metrics.dec("b")?;
+
+        let json = serde_json::to_string(&metrics)?;
+        let value: serde_json::Value = serde_json::from_str(&json)?;
+        assert_eq!(value, serde_json::json!({"a": 1, "b": -1}));
+        Ok(())
+    }
+
+    #[test]
+    fn test_inc_distinct_keys_from_threads() -> Result<()> {
+        let metrics = CmapMetrics::new();
+        let handles = (0..8)
+            .map(|t| {
+                let metrics = metrics.clone();
+                thread::spawn(move || {
+                    for i in 0..1000 {
+                        metrics.inc(format!("thread.{}.key.{}", t, i % 4))?;
+                    }
+                    Ok::<_, anyhow::Error>(())
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle
+                .join()
+                .map_err(|e| anyhow::anyhow!("Thread join error {:?}", e))??;
+        }
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.len(), 8 * 4);
+        assert!(snapshot.values().all(|&v| v == 250));
         Ok(())
     }
 }
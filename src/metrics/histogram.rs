@@ -0,0 +1,181 @@
+use std::{
+    fmt::Write,
+    sync::{
+        atomic::{AtomicI64, AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use crate::error::{ConcurrencyError, Result};
+
+/// A histogram with fixed upper bucket bounds, plus an implicit `+Inf` bucket
+/// for everything above the last one.
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    bounds: Vec<f64>,
+    // one more than bounds, the last one is +Inf
+    counts: Vec<AtomicI64>,
+    // f64 bits, updated with a compare-exchange loop
+    sum: AtomicU64,
+}
+
+/// Per-bucket (not cumulative) counts, keyed by each bucket's upper bound.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramSnapshot {
+    pub buckets: Vec<(f64, i64)>,
+    pub count: i64,
+    pub sum: f64,
+}
+
+impl Histogram {
+    /// Bucket bounds must be finite and strictly increasing; the `+Inf` bucket
+    /// is always added on top.
+    pub fn new(buckets: &[f64]) -> Result<Self> {
+        if buckets.iter().any(|b| !b.is_finite()) {
+            return Err(ConcurrencyError::InvalidArgument(
+                "buckets must be finite".to_string(),
+            ));
+        }
+        if buckets.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(ConcurrencyError::InvalidArgument(
+                "buckets must be strictly increasing".to_string(),
+            ));
+        }
+
+        let inner = Inner {
+            bounds: buckets.to_vec(),
+            counts: (0..=buckets.len()).map(|_| AtomicI64::new(0)).collect(),
+            sum: AtomicU64::new(0f64.to_bits()),
+        };
+        Ok(Self {
+            inner: Arc::new(inner),
+        })
+    }
+
+    /// Records `value`. NaN and infinities are rejected, since either would
+    /// poison the running sum for good.
+    pub fn observe(&self, value: f64) -> Result<()> {
+        if !value.is_finite() {
+            return Err(ConcurrencyError::InvalidArgument(format!(
+                "cannot observe non-finite value {}",
+                value
+            )));
+        }
+
+        let idx = self.inner.bounds.partition_point(|&b| b < value);
+        self.inner.counts[idx].fetch_add(1, Ordering::Relaxed);
+        let _ = self
+            .inner
+            .sum
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                Some((f64::from_bits(bits) + value).to_bits())
+            });
+        Ok(())
+    }
+
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        let bounds = self.inner.bounds.iter().copied().chain([f64::INFINITY]);
+        let buckets = bounds
+            .zip(&self.inner.counts)
+            .map(|(bound, count)| (bound, count.load(Ordering::Relaxed)))
+            .collect::<Vec<_>>();
+        HistogramSnapshot {
+            count: buckets.iter().map(|(_, c)| c).sum(),
+            buckets,
+            sum: f64::from_bits(self.inner.sum.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Renders cumulative `_bucket` lines followed by `_sum` and `_count`.
+    pub fn to_prometheus(&self, name: &str) -> String {
+        let name = super::sanitize_metric_name(name);
+        let snapshot = self.snapshot();
+        let mut out = String::new();
+        // writing into a String never fails
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        let mut cumulative = 0;
+        for (bound, count) in snapshot.buckets {
+            cumulative += count;
+            let le = if bound.is_infinite() {
+                "+Inf".to_string()
+            } else {
+                bound.to_string()
+            };
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, le, cumulative);
+        }
+        let _ = writeln!(out, "{}_sum {}", name, snapshot.sum);
+        let _ = writeln!(out, "{}_count {}", name, snapshot.count);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_histogram_observe() -> Result<()> {
+        let histogram = Histogram::new(&[0.1, 0.5, 1.0])?;
+        for value in [0.05, 0.1, 0.3, 0.7, 2.0, 3.0] {
+            histogram.observe(value)?;
+        }
+
+        let snapshot = histogram.snapshot();
+        assert_eq!(
+            snapshot.buckets,
+            vec![(0.1, 2), (0.5, 1), (1.0, 1), (f64::INFINITY, 2)]
+        );
+        assert_eq!(snapshot.count, 6);
+        assert!((snapshot.sum - 6.15).abs() < 1e-9);
+        Ok(())
+    }
+
+    #[test]
+    fn test_histogram_rejects_non_finite() -> Result<()> {
+        let histogram = Histogram::new(&[1.0])?;
+        histogram.observe(0.5)?;
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(matches!(
+                histogram.observe(value),
+                Err(ConcurrencyError::InvalidArgument(_))
+            ));
+        }
+
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.count, 1);
+        assert_eq!(snapshot.sum, 0.5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_histogram_to_prometheus() -> Result<()> {
+        let histogram = Histogram::new(&[0.5, 1.0])?;
+        histogram.observe(0.25)?;
+        histogram.observe(2.0)?;
+        assert_eq!(
+            histogram.to_prometheus("request.latency"),
+            "# TYPE request_latency histogram\n\
+             request_latency_bucket{le=\"0.5\"} 1\n\
+             request_latency_bucket{le=\"1\"} 1\n\
+             request_latency_bucket{le=\"+Inf\"} 2\n\
+             request_latency_sum 2.25\n\
+             request_latency_count 2\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_histogram_invalid_buckets() {
+        assert!(Histogram::new(&[1.0, 0.5]).is_err());
+        assert!(Histogram::new(&[0.5, 0.5]).is_err());
+        assert!(Histogram::new(&[f64::NAN]).is_err());
+        assert!(Histogram::new(&[1.0, f64::INFINITY]).is_err());
+        assert!(Histogram::new(&[f64::NEG_INFINITY, 1.0]).is_err());
+    }
+}
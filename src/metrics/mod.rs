@@ -1,5 +1,141 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Write},
+};
+
+use crate::error::Result;
+
 pub mod amap;
 pub mod cmap;
+pub mod histogram;
+pub mod registry;
+pub mod sharded;
 
 pub use amap::*;
 pub use cmap::*;
+pub use histogram::*;
+pub use registry::*;
+pub use sharded::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+}
+
+impl fmt::Display for MetricKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetricKind::Counter => write!(f, "counter"),
+            MetricKind::Gauge => write!(f, "gauge"),
+        }
+    }
+}
+
+/// The operations every metrics backend supports, for code that is generic over
+/// which one it records into.
+pub trait Metrics {
+    fn inc(&self, key: impl AsRef<str>) -> Result<()>;
+    fn snapshot(&self) -> HashMap<String, i64>;
+}
+
+impl Metrics for AmapMetrics {
+    fn inc(&self, key: impl AsRef<str>) -> Result<()> {
+        AmapMetrics::inc(self, key)
+    }
+
+    fn snapshot(&self) -> HashMap<String, i64> {
+        AmapMetrics::snapshot(self)
+    }
+}
+
+impl Metrics for CmapMetrics {
+    fn inc(&self, key: impl AsRef<str>) -> Result<()> {
+        CmapMetrics::inc(self, key.as_ref())
+    }
+
+    fn snapshot(&self) -> HashMap<String, i64> {
+        CmapMetrics::snapshot(self)
+    }
+}
+
+impl Metrics for ShardedMetrics {
+    fn inc(&self, key: impl AsRef<str>) -> Result<()> {
+        ShardedMetrics::inc(self, key)
+    }
+
+    fn snapshot(&self) -> HashMap<String, i64> {
+        ShardedMetrics::snapshot(self)
+    }
+}
+
+impl Metrics for MetricsRegistry {
+    fn inc(&self, key: impl AsRef<str>) -> Result<()> {
+        MetricsRegistry::inc(self, key)
+    }
+
+    fn snapshot(&self) -> HashMap<String, i64> {
+        MetricsRegistry::snapshot(self)
+    }
+}
+
+pub(crate) fn to_prometheus<K: AsRef<str>>(mut entries: Vec<(K, i64, MetricKind)>) -> String {
+    entries.sort_by(|a, b| a.0.as_ref().cmp(b.0.as_ref()));
+    let mut out = String::new();
+    for (key, value, kind) in entries {
+        let name = sanitize_metric_name(key.as_ref());
+        // writing into a String never fails
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        let _ = writeln!(out, "{} {}", name, value);
+    }
+    out
+}
+
+// prometheus metric names must match [a-zA-Z_:][a-zA-Z0-9_:]*
+fn sanitize_metric_name(name: &str) -> String {
+    let mut sanitized = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == ':' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if sanitized.is_empty() || sanitized.starts_with(|c: char| c.is_ascii_digit()) {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record_requests<M: Metrics>(metrics: &M) -> Result<i64> {
+        for _ in 0..3 {
+            metrics.inc("requests")?;
+        }
+        Ok(metrics.snapshot()["requests"])
+    }
+
+    #[test]
+    fn test_metrics_trait_is_generic() -> anyhow::Result<()> {
+        assert_eq!(record_requests(&AmapMetrics::new(&["requests"]))?, 3);
+        assert_eq!(record_requests(&CmapMetrics::new())?, 3);
+        assert_eq!(record_requests(&ShardedMetrics::new(&["requests"]))?, 3);
+
+        let registry = MetricsRegistry::new();
+        registry.register_counter("requests")?;
+        assert_eq!(record_requests(&registry)?, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sanitize_metric_name() {
+        assert_eq!(sanitize_metric_name("http_requests"), "http_requests");
+        assert_eq!(sanitize_metric_name("req.page-1"), "req_page_1");
+        assert_eq!(sanitize_metric_name("1xx"), "_1xx");
+    }
+}
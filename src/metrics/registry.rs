@@ -0,0 +1,152 @@
+use core::fmt;
+use dashmap::DashMap;
+use std::{collections::HashMap, sync::Arc};
+
+use super::MetricKind;
+use crate::error::{ConcurrencyError, Result};
+
+/// Named counters and gauges registered at runtime. `inc`/`dec` only apply to
+/// counters and `set` only to gauges; anything else is a `KindMismatch`.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsRegistry {
+    data: Arc<DashMap<String, Metric>>,
+}
+
+#[derive(Debug)]
+struct Metric {
+    kind: MetricKind,
+    value: i64,
+}
+
+impl MetricsRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register_counter(&self, key: impl Into<String>) -> Result<()> {
+        self.register(key.into(), MetricKind::Counter)
+    }
+
+    pub fn register_gauge(&self, key: impl Into<String>) -> Result<()> {
+        self.register(key.into(), MetricKind::Gauge)
+    }
+
+    // registering an existing name again is a no-op as long as the kind agrees
+    fn register(&self, key: String, kind: MetricKind) -> Result<()> {
+        let metric = self
+            .data
+            .entry(key.clone())
+            .or_insert(Metric { kind, value: 0 });
+        if metric.kind != kind {
+            return Err(ConcurrencyError::KindMismatch {
+                key,
+                kind: metric.kind,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn inc(&self, key: impl AsRef<str>) -> Result<()> {
+        self.update(key.as_ref(), MetricKind::Counter, |v| *v += 1)
+    }
+
+    pub fn dec(&self, key: impl AsRef<str>) -> Result<()> {
+        self.update(key.as_ref(), MetricKind::Counter, |v| *v -= 1)
+    }
+
+    pub fn set(&self, key: impl AsRef<str>, value: i64) -> Result<()> {
+        self.update(key.as_ref(), MetricKind::Gauge, |v| *v = value)
+    }
+
+    fn update(&self, key: &str, kind: MetricKind, f: impl FnOnce(&mut i64)) -> Result<()> {
+        let mut metric = self
+            .data
+            .get_mut(key)
+            .ok_or_else(|| ConcurrencyError::KeyNotFound(key.to_string()))?;
+        if metric.kind != kind {
+            return Err(ConcurrencyError::KindMismatch {
+                key: key.to_string(),
+                kind: metric.kind,
+            });
+        }
+        f(&mut metric.value);
+        Ok(())
+    }
+
+    pub fn snapshot(&self) -> HashMap<String, i64> {
+        self.data
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().value))
+            .collect()
+    }
+
+    pub fn to_prometheus(&self) -> String {
+        super::to_prometheus(self.entries())
+    }
+
+    fn entries(&self) -> Vec<(String, i64, MetricKind)> {
+        self.data
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value, entry.kind))
+            .collect()
+    }
+}
+
+impl fmt::Display for MetricsRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut entries = self.entries();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        for (key, value, kind) in entries {
+            match kind {
+                MetricKind::Counter => writeln!(f, "{}: {}", key, value)?,
+                MetricKind::Gauge => writeln!(f, "{}: {} (gauge)", key, value)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_registry_counter_and_gauge() -> Result<()> {
+        let registry = MetricsRegistry::new();
+        registry.register_counter("requests")?;
+        registry.register_gauge("inflight")?;
+
+        registry.inc("requests")?;
+        registry.inc("requests")?;
+        registry.set("inflight", 7)?;
+
+        assert_eq!(registry.snapshot()["requests"], 2);
+        assert_eq!(registry.snapshot()["inflight"], 7);
+        assert_eq!(registry.to_string(), "inflight: 7 (gauge)\nrequests: 2\n");
+        assert_eq!(
+            registry.to_prometheus(),
+            "# TYPE inflight gauge\ninflight 7\n# TYPE requests counter\nrequests 2\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_registry_errors() -> Result<()> {
+        let registry = MetricsRegistry::new();
+        registry.register_counter("requests")?;
+        registry.register_gauge("inflight")?;
+
+        assert_eq!(
+            registry.inc("inflight"),
+            Err(ConcurrencyError::KindMismatch {
+                key: "inflight".to_string(),
+                kind: MetricKind::Gauge,
+            })
+        );
+        assert!(registry.set("requests", 1).is_err());
+        assert!(registry.register_gauge("requests").is_err());
+        assert!(registry.inc("missing").is_err());
+        Ok(())
+    }
+}
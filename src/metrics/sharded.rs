@@ -0,0 +1,171 @@
+use core::fmt;
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicI64, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use super::MetricKind;
+use crate::{
+    error::{ConcurrencyError, Result},
+    matrix::default_num_threads,
+};
+
+/// Counters split into one shard per core so threads incrementing the same key
+/// mostly touch different atomics. Writes stay cheap under contention; reads
+/// pay for it by summing every shard.
+pub struct ShardedMetrics {
+    index: Arc<HashMap<String, usize>>,
+    shards: Arc<Vec<Shard>>,
+}
+
+struct Shard(Vec<PaddedCounter>);
+
+// each counter gets a cache line to itself, so threads bumping different
+// shards (or different keys) never contend on the same line
+#[repr(align(64))]
+struct PaddedCounter(AtomicI64);
+
+static NEXT_SHARD: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    // handed out round-robin the first time a thread touches any ShardedMetrics
+    static SHARD: Cell<usize> = Cell::new(NEXT_SHARD.fetch_add(1, Ordering::Relaxed));
+}
+
+impl Clone for ShardedMetrics {
+    fn clone(&self) -> Self {
+        ShardedMetrics {
+            index: Arc::clone(&self.index),
+            shards: Arc::clone(&self.shards),
+        }
+    }
+}
+
+impl ShardedMetrics {
+    pub fn new(metric_names: &[&str]) -> Self {
+        Self::with_shards(metric_names, default_num_threads())
+    }
+
+    pub fn with_shards(metric_names: &[&str], num_shards: usize) -> Self {
+        let index = metric_names
+            .iter()
+            .enumerate()
+            .map(|(i, &name)| (name.to_string(), i))
+            .collect::<HashMap<_, _>>();
+        let shards = (0..num_shards.max(1))
+            .map(|_| {
+                Shard(
+                    (0..metric_names.len())
+                        .map(|_| PaddedCounter(AtomicI64::new(0)))
+                        .collect(),
+                )
+            })
+            .collect();
+        ShardedMetrics {
+            index: Arc::new(index),
+            shards: Arc::new(shards),
+        }
+    }
+
+    pub fn inc(&self, key: impl AsRef<str>) -> Result<()> {
+        self.add(key, 1)
+    }
+
+    pub fn dec(&self, key: impl AsRef<str>) -> Result<()> {
+        self.add(key, -1)
+    }
+
+    pub fn add(&self, key: impl AsRef<str>, delta: i64) -> Result<()> {
+        let key = key.as_ref();
+        let idx = *self
+            .index
+            .get(key)
+            .ok_or_else(|| ConcurrencyError::KeyNotFound(key.to_string()))?;
+        let shard = SHARD.with(|s| s.get()) % self.shards.len();
+        self.shards[shard].0[idx]
+            .0
+            .fetch_add(delta, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Merges every shard. Increments racing with the merge may or may not be
+    /// included, but none is ever lost.
+    pub fn snapshot(&self) -> HashMap<String, i64> {
+        self.index
+            .iter()
+            .map(|(key, &idx)| {
+                let total = self
+                    .shards
+                    .iter()
+                    .map(|shard| shard.0[idx].0.load(Ordering::Relaxed))
+                    .sum();
+                (key.clone(), total)
+            })
+            .collect()
+    }
+
+    pub fn to_prometheus(&self) -> String {
+        let entries = self
+            .snapshot()
+            .into_iter()
+            .map(|(key, value)| (key, value, MetricKind::Counter))
+            .collect();
+        super::to_prometheus(entries)
+    }
+}
+
+impl fmt::Display for ShardedMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut entries = self.snapshot().into_iter().collect::<Vec<_>>();
+        entries.sort();
+        for (key, value) in entries {
+            writeln!(f, "{}: {}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::thread;
+
+    #[test]
+    fn test_sharded_totals_across_threads() -> Result<()> {
+        let metrics = ShardedMetrics::with_shards(&["requests", "errors"], 4);
+        let handles = (0..8)
+            .map(|_| {
+                let metrics = metrics.clone();
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        metrics.inc("requests")?;
+                    }
+                    metrics.inc("errors")
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle
+                .join()
+                .map_err(|e| anyhow::anyhow!("Thread join error {:?}", e))??;
+        }
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot["requests"], 8000);
+        assert_eq!(snapshot["errors"], 8);
+        assert_eq!(metrics.to_string(), "errors: 8\nrequests: 8000\n");
+        assert!(metrics.inc("missing").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_counters_fill_a_cache_line_each() {
+        assert_eq!(std::mem::size_of::<PaddedCounter>(), 64);
+        assert_eq!(std::mem::align_of::<PaddedCounter>(), 64);
+    }
+}
@@ -0,0 +1,236 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use crate::error::{ConcurrencyError, Result};
+
+/// Runs `num_producers` producer threads feeding a single consumer thread.
+///
+/// Each producer calls `producer(idx)` until it returns `Ok(None)`, fails, or
+/// `stop` is set. Every producer error is collected and returned together once
+/// all threads have been joined. A panicking consumer is reported as
+/// [`ConcurrencyError::Consumer`] instead, since the producers then only stop
+/// because nobody is receiving.
+pub fn run_pipeline<M, P, C>(
+    num_producers: usize,
+    stop: Arc<AtomicBool>,
+    producer: P,
+    mut consumer: C,
+) -> Result<()>
+where
+    M: Send + 'static,
+    P: Fn(usize) -> Result<Option<M>> + Send + Sync + 'static,
+    C: FnMut(M) + Send + 'static,
+{
+    let producer = Arc::new(producer);
+    let (tx, rx) = mpsc::channel();
+    let producers = (0..num_producers)
+        .map(|idx| {
+            let tx = tx.clone();
+            let stop = stop.clone();
+            let producer = producer.clone();
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    match producer(idx)? {
+                        Some(msg) => {
+                            // the consumer only goes away if it panicked
+                            if tx.send(msg).is_err() {
+                                break;
+                            }
+                        }
+                        None => break,
+                    }
+                }
+                Ok::<_, ConcurrencyError>(())
+            })
+        })
+        .collect::<Vec<_>>();
+    // the consumer loop ends once every producer's sender is gone
+    drop(tx);
+
+    let consumer = thread::spawn(move || {
+        for msg in rx {
+            consumer(msg);
+        }
+    });
+
+    let mut errors = Vec::new();
+    for handle in producers {
+        match handle.join() {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => errors.push(e),
+            Err(e) => errors.push(ConcurrencyError::Worker(format!(
+                "producer join error {:?}",
+                e
+            ))),
+        }
+    }
+    if let Err(e) = consumer.join() {
+        return Err(ConcurrencyError::Consumer(format!(
+            "consumer join error {:?}",
+            e
+        )));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ConcurrencyError::Producers(errors))
+    }
+}
+
+/// Spawns `count` producer threads, each draining the iterator `make_iter(idx)`
+/// into a shared channel, and a consumer thread that is handed the receiving
+/// end. The receiver runs dry once every producer's iterator is exhausted.
+///
+/// The returned thread joins every producer after the consumer returns, so a
+/// panicking producer is reported as [`ConcurrencyError::Producers`] rather
+/// than just a channel that ran dry early.
+pub fn spawn_producers<I, F, C, R>(count: usize, make_iter: F, consumer: C) -> JoinHandle<Result<R>>
+where
+    I: Iterator,
+    I::Item: Send + 'static,
+    F: Fn(usize) -> I + Send + Sync + 'static,
+    C: FnOnce(mpsc::Receiver<I::Item>) -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let make_iter = Arc::new(make_iter);
+    let (tx, rx) = mpsc::channel();
+    let producers = (0..count)
+        .map(|idx| {
+            let tx = tx.clone();
+            let make_iter = make_iter.clone();
+            thread::spawn(move || {
+                for item in make_iter(idx) {
+                    // the consumer only goes away if it panicked or stopped early
+                    if tx.send(item).is_err() {
+                        break;
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    drop(tx);
+
+    thread::spawn(move || {
+        let ret = consumer(rx);
+        // the receiver is gone by now, so every producer stops at its next send
+        let errors = producers
+            .into_iter()
+            .filter_map(|handle| handle.join().err())
+            .map(|e| ConcurrencyError::Worker(format!("producer join error {:?}", e)))
+            .collect::<Vec<_>>();
+        if errors.is_empty() {
+            Ok(ret)
+        } else {
+            Err(ConcurrencyError::Producers(errors))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::sync::{atomic::AtomicUsize, Mutex};
+
+    #[test]
+    fn test_run_pipeline_delivers_all_messages() -> Result<()> {
+        let sent = Arc::new((0..4).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>());
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let counters = sent.clone();
+        let sink = received.clone();
+        run_pipeline(
+            4,
+            Arc::new(AtomicBool::new(false)),
+            move |idx| {
+                let n = counters[idx].fetch_add(1, Ordering::Relaxed);
+                Ok((n < 10).then_some((idx, n)))
+            },
+            move |msg| sink.lock().unwrap().push(msg),
+        )?;
+
+        let mut received = received.lock().unwrap().clone();
+        received.sort();
+        let expected = (0..4)
+            .flat_map(|idx| (0..10).map(move |n| (idx, n)))
+            .collect::<Vec<_>>();
+        assert_eq!(received, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_pipeline_stops_and_collects_errors() {
+        let stop = Arc::new(AtomicBool::new(false));
+        let flag = stop.clone();
+        let ret = run_pipeline(
+            3,
+            stop,
+            move |idx| {
+                if idx == 0 {
+                    return Err(ConcurrencyError::InvalidArgument("bad input".to_string()));
+                }
+                // keep producing until the stop flag is raised
+                Ok(Some(idx))
+            },
+            move |_| flag.store(true, Ordering::Relaxed),
+        );
+
+        assert_eq!(
+            ret,
+            Err(ConcurrencyError::Producers(vec![
+                ConcurrencyError::InvalidArgument("bad input".to_string())
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_run_pipeline_reports_consumer_panic() {
+        let ret = run_pipeline(
+            2,
+            Arc::new(AtomicBool::new(false)),
+            |_| Ok(Some(1)),
+            |_: i32| panic!("consumer blew up"),
+        );
+        assert!(matches!(ret, Err(ConcurrencyError::Consumer(_))));
+    }
+
+    #[test]
+    fn test_spawn_producers_drains_iterators() -> Result<()> {
+        let consumer = spawn_producers(
+            4,
+            |idx| (0..10).map(move |n| (idx, n)),
+            |rx| rx.iter().count(),
+        );
+        let count = consumer
+            .join()
+            .map_err(|e| anyhow::anyhow!("Thread join error {:?}", e))??;
+        assert_eq!(count, 4 * 10);
+        Ok(())
+    }
+
+    #[test]
+    fn test_spawn_producers_reports_producer_panics() -> Result<()> {
+        let consumer = spawn_producers(
+            3,
+            |idx| {
+                (0..10).inspect(move |&n| {
+                    if idx > 0 && n == 5 {
+                        panic!("producer {} blew up", idx);
+                    }
+                })
+            },
+            |rx| rx.iter().count(),
+        );
+        let ret = consumer
+            .join()
+            .map_err(|e| anyhow::anyhow!("Thread join error {:?}", e))?;
+        assert!(matches!(ret, Err(ConcurrencyError::Producers(errors)) if errors.len() == 2));
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Arc, Mutex},
+    thread::{self, JoinHandle},
+};
+
+use crate::error::{ConcurrencyError, Result};
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// A fixed-size pool of worker threads pulling jobs from a shared queue.
+///
+/// Dropping the pool closes the queue, lets the workers drain whatever is
+/// still pending and then joins them.
+pub struct ThreadPool {
+    sender: Option<mpsc::Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl ThreadPool {
+    pub fn new(size: usize) -> Result<Self> {
+        if size == 0 {
+            return Err(ConcurrencyError::InvalidArgument(
+                "size must be greater than 0".to_string(),
+            ));
+        }
+
+        let (tx, rx) = mpsc::channel::<Job>();
+        let rx = Arc::new(Mutex::new(rx));
+        let workers = (0..size)
+            .map(|_| {
+                let rx = rx.clone();
+                thread::spawn(move || loop {
+                    // the lock is released before the job runs
+                    let job = match rx.lock() {
+                        Ok(rx) => rx.recv(),
+                        Err(_) => break,
+                    };
+                    match job {
+                        // a panicking job must not take the worker down with it
+                        Ok(job) => {
+                            let _ = panic::catch_unwind(AssertUnwindSafe(job));
+                        }
+                        Err(_) => break,
+                    }
+                })
+            })
+            .collect();
+
+        Ok(Self {
+            sender: Some(tx),
+            workers,
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
+
+    pub fn execute(&self, job: impl FnOnce() + Send + 'static) -> Result<()> {
+        self.sender
+            .as_ref()
+            .and_then(|tx| tx.send(Box::new(job)).ok())
+            .ok_or_else(|| ConcurrencyError::Worker("thread pool is shut down".to_string()))
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        // closing the sender ends the workers' receive loops once the queue is empty
+        drop(self.sender.take());
+        for worker in self.workers.drain(..) {
+            // a drop has nowhere to return an error to, so it is only traced
+            let _result = worker.join();
+            #[cfg(feature = "tracing")]
+            if let Err(e) = _result {
+                tracing::warn!(error = ?e, "worker join error");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    #[test]
+    fn test_thread_pool_runs_all_jobs_before_drop() -> Result<()> {
+        let counter = Arc::new(AtomicI64::new(0));
+        let pool = ThreadPool::new(4)?;
+        assert_eq!(pool.size(), 4);
+        for _ in 0..100 {
+            let counter = counter.clone();
+            pool.execute(move || {
+                counter.fetch_add(1, Ordering::Relaxed);
+            })?;
+        }
+        drop(pool);
+        assert_eq!(counter.load(Ordering::Relaxed), 100);
+        Ok(())
+    }
+
+    #[test]
+    fn test_thread_pool_survives_panicking_job() -> Result<()> {
+        let counter = Arc::new(AtomicI64::new(0));
+        let pool = ThreadPool::new(1)?;
+        pool.execute(|| panic!("boom"))?;
+        let c = counter.clone();
+        pool.execute(move || {
+            c.fetch_add(1, Ordering::Relaxed);
+        })?;
+        drop(pool);
+        assert_eq!(counter.load(Ordering::Relaxed), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_thread_pool_zero_size() {
+        assert!(ThreadPool::new(0).is_err());
+    }
+}
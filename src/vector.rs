@@ -1,8 +1,14 @@
-use anyhow::anyhow;
-use anyhow::Result;
+use num_traits::{Float, ToPrimitive};
 use std::{
     fmt::Debug,
-    ops::{Add, AddAssign, Deref, Mul},
+    ops::{Add, AddAssign, Deref, Mul, Sub},
+    thread,
+};
+
+use crate::{
+    error::{ConcurrencyError, Result},
+    matrix::{default_num_threads, join_all},
+    Matrix,
 };
 
 pub struct Vector<T> {
@@ -14,13 +20,160 @@ impl<T> Vector<T> {
         Self { data: data.into() }
     }
 
-    // pub fn len(&self) -> usize {
-    //     self.data.len()
-    // }
-    //
-    // pub fn iter(&self) -> std::slice::Iter<T> {
-    //     self.data.iter()
-    // }
+    pub fn norm(&self) -> T
+    where
+        T: Float,
+    {
+        self.data
+            .iter()
+            .fold(T::zero(), |acc, &x| acc + x * x)
+            .sqrt()
+    }
+
+    /// True if any element is NaN or infinite.
+    pub fn has_non_finite(&self) -> bool
+    where
+        T: Float,
+    {
+        self.data.iter().any(|v| !v.is_finite())
+    }
+
+    pub fn normalize(&self) -> Result<Vector<T>>
+    where
+        T: Float,
+    {
+        let norm = self.norm();
+        if norm.is_zero() {
+            return Err(ConcurrencyError::ZeroVector);
+        }
+        if !norm.is_finite() {
+            return Err(ConcurrencyError::InvalidArgument(
+                "cannot normalize a vector with a non-finite norm".to_string(),
+            ));
+        }
+        Ok(self.data.iter().map(|&x| x / norm).collect())
+    }
+
+    pub fn scale(&self, factor: T) -> Vector<T>
+    where
+        T: Copy + Mul<Output = T>,
+    {
+        self.data.iter().map(|&x| x * factor).collect()
+    }
+
+    pub fn add(&self, other: &Vector<T>) -> Result<Vector<T>>
+    where
+        T: Copy + Add<Output = T>,
+    {
+        self.zip_with(other, |x, y| x + y)
+    }
+
+    pub fn sub(&self, other: &Vector<T>) -> Result<Vector<T>>
+    where
+        T: Copy + Sub<Output = T>,
+    {
+        self.zip_with(other, |x, y| x - y)
+    }
+
+    fn zip_with(&self, other: &Vector<T>, f: impl Fn(T, T) -> T) -> Result<Vector<T>>
+    where
+        T: Copy,
+    {
+        if self.data.len() != other.data.len() {
+            return Err(ConcurrencyError::LengthMismatch {
+                expected: self.data.len(),
+                got: other.data.len(),
+            });
+        }
+        Ok(self
+            .data
+            .iter()
+            .zip(other.data.iter())
+            .map(|(&x, &y)| f(x, y))
+            .collect())
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.data.iter()
+    }
+
+    /// Splits into sub-vectors of `size` elements, the last one possibly shorter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    pub fn chunks(&self, size: usize) -> Vec<Vector<T>>
+    where
+        T: Clone,
+    {
+        assert!(size > 0, "chunk size must be greater than 0");
+        self.data.chunks(size).map(Vector::new).collect()
+    }
+
+    pub fn sum(&self) -> T
+    where
+        T: Copy + Default + AddAssign,
+    {
+        let mut sum = T::default();
+        for &x in &self.data {
+            sum += x;
+        }
+        sum
+    }
+
+    pub fn mean(&self) -> Result<f64>
+    where
+        T: Copy + Default + AddAssign + ToPrimitive,
+    {
+        if self.is_empty() {
+            return Err(ConcurrencyError::EmptyInput);
+        }
+        let sum = self.sum().to_f64().ok_or_else(|| {
+            ConcurrencyError::InvalidArgument("sum is not representable as f64".to_string())
+        })?;
+        Ok(sum / self.len() as f64)
+    }
+
+    /// The smallest element; with incomparable values (NaN) the first wins.
+    pub fn min(&self) -> Option<&T>
+    where
+        T: PartialOrd,
+    {
+        self.data.iter().reduce(|m, x| if x < m { x } else { m })
+    }
+
+    /// The largest element; with incomparable values (NaN) the first wins.
+    pub fn max(&self) -> Option<&T>
+    where
+        T: PartialOrd,
+    {
+        self.data.iter().reduce(|m, x| if x > m { x } else { m })
+    }
+}
+
+impl<T> Debug for Vector<T>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Vector({:?})", self.data)
+    }
+}
+
+impl<T: Clone> Clone for Vector<T> {
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+        }
+    }
 }
 
 impl<T> Deref for Vector<T> {
@@ -31,12 +184,71 @@ impl<T> Deref for Vector<T> {
     }
 }
 
+impl<T> From<Vec<T>> for Vector<T> {
+    fn from(data: Vec<T>) -> Self {
+        Self { data }
+    }
+}
+
+impl<T: Clone> From<&[T]> for Vector<T> {
+    fn from(data: &[T]) -> Self {
+        Self::new(data)
+    }
+}
+
+impl<T> FromIterator<T> for Vector<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl<T> IntoIterator for Vector<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Vector<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.iter()
+    }
+}
+
+#[must_use = "dot_product does nothing besides computing its result"]
 pub fn dot_product<T>(a: Vector<T>, b: Vector<T>) -> Result<T>
+where
+    T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
+{
+    #[cfg(feature = "simd")]
+    if a.len() == b.len() && !a.is_empty() {
+        if let Some(sum) = simd::dot(&a, &b) {
+            return Ok(sum);
+        }
+    }
+
+    dot_slice(&a, &b)
+}
+
+/// [`dot_product`] over plain slices, for callers that already hold the data
+/// and don't want to allocate a `Vector`.
+pub fn dot_slice<T>(a: &[T], b: &[T]) -> Result<T>
 where
     T: Debug + Copy + Default + Add<Output = T> + AddAssign + Mul<Output = T> + Send + 'static,
 {
     if a.len() != b.len() {
-        return Err(anyhow!("Matrix multiply error : a.len != b.len"));
+        return Err(ConcurrencyError::LengthMismatch {
+            expected: a.len(),
+            got: b.len(),
+        });
+    }
+    if a.is_empty() {
+        return Err(ConcurrencyError::EmptyInput);
     }
 
     let mut sum = T::default();
@@ -46,3 +258,590 @@ where
 
     Ok(sum)
 }
+
+/// Element types with overflow-checked arithmetic, see [`dot_product_checked`].
+pub trait CheckedNum: Copy + Default {
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+}
+
+impl<T> CheckedNum for T
+where
+    T: Copy + Default + num_traits::CheckedAdd + num_traits::CheckedMul,
+{
+    fn checked_add(self, rhs: Self) -> Option<Self> {
+        num_traits::CheckedAdd::checked_add(&self, &rhs)
+    }
+
+    fn checked_mul(self, rhs: Self) -> Option<Self> {
+        num_traits::CheckedMul::checked_mul(&self, &rhs)
+    }
+}
+
+/// Like [`dot_product`], but returns [`ConcurrencyError::Overflow`] instead of
+/// wrapping when a product or the running sum overflows.
+pub fn dot_product_checked<T: CheckedNum>(a: &Vector<T>, b: &Vector<T>) -> Result<T> {
+    if a.len() != b.len() {
+        return Err(ConcurrencyError::LengthMismatch {
+            expected: a.len(),
+            got: b.len(),
+        });
+    }
+    if a.is_empty() {
+        return Err(ConcurrencyError::EmptyInput);
+    }
+
+    a.iter()
+        .zip(b.iter())
+        .try_fold(T::default(), |sum, (&x, &y)| {
+            x.checked_mul(y)
+                .and_then(|p| sum.checked_add(p))
+                .ok_or(ConcurrencyError::Overflow)
+        })
+}
+
+/// Element types with saturating arithmetic, see [`dot_product_saturating`].
+pub trait SaturatingNum: Copy + Default {
+    fn saturating_add(self, rhs: Self) -> Self;
+    fn saturating_mul(self, rhs: Self) -> Self;
+}
+
+impl<T> SaturatingNum for T
+where
+    T: Copy + Default + num_traits::SaturatingAdd + num_traits::SaturatingMul,
+{
+    fn saturating_add(self, rhs: Self) -> Self {
+        num_traits::SaturatingAdd::saturating_add(&self, &rhs)
+    }
+
+    fn saturating_mul(self, rhs: Self) -> Self {
+        num_traits::SaturatingMul::saturating_mul(&self, &rhs)
+    }
+}
+
+/// Like [`dot_product`], but clamps every product and partial sum to the
+/// type's bounds instead of wrapping.
+pub fn dot_product_saturating<T: SaturatingNum>(a: &Vector<T>, b: &Vector<T>) -> Result<T> {
+    if a.len() != b.len() {
+        return Err(ConcurrencyError::LengthMismatch {
+            expected: a.len(),
+            got: b.len(),
+        });
+    }
+    if a.is_empty() {
+        return Err(ConcurrencyError::EmptyInput);
+    }
+
+    Ok(a.iter().zip(b.iter()).fold(T::default(), |sum, (&x, &y)| {
+        sum.saturating_add(x.saturating_mul(y))
+    }))
+}
+
+#[cfg(feature = "simd")]
+mod simd {
+    use std::any::Any;
+    use wide::{f32x8, f64x4};
+
+    use super::Vector;
+
+    macro_rules! simd_dot {
+        ($name:ident, $t:ty, $simd:ty, $lanes:expr) => {
+            fn $name(a: &[$t], b: &[$t]) -> $t {
+                let mut acc = <$simd>::splat(0.0);
+                let (a_chunks, b_chunks) = (a.chunks_exact($lanes), b.chunks_exact($lanes));
+                let (a_rest, b_rest) = (a_chunks.remainder(), b_chunks.remainder());
+                for (x, y) in a_chunks.zip(b_chunks) {
+                    let x = <$simd>::new(x.try_into().expect("chunks_exact yields full lanes"));
+                    let y = <$simd>::new(y.try_into().expect("chunks_exact yields full lanes"));
+                    acc += x * y;
+                }
+                let mut sum = acc.reduce_add();
+                for (x, y) in a_rest.iter().zip(b_rest) {
+                    sum += x * y;
+                }
+                sum
+            }
+        };
+    }
+
+    simd_dot!(dot_f32, f32, f32x8, 8);
+    simd_dot!(dot_f64, f64, f64x4, 4);
+
+    // only f32 and f64 have a vectorized path, every other T returns None
+    pub(super) fn dot<T: Copy + 'static>(a: &Vector<T>, b: &Vector<T>) -> Option<T> {
+        let (a, b) = (a as &dyn Any, b as &dyn Any);
+        if let (Some(a), Some(b)) = (
+            a.downcast_ref::<Vector<f32>>(),
+            b.downcast_ref::<Vector<f32>>(),
+        ) {
+            return (&dot_f32(a, b) as &dyn Any).downcast_ref::<T>().copied();
+        }
+        if let (Some(a), Some(b)) = (
+            a.downcast_ref::<Vector<f64>>(),
+            b.downcast_ref::<Vector<f64>>(),
+        ) {
+            return (&dot_f64(a, b) as &dyn Any).downcast_ref::<T>().copied();
+        }
+        None
+    }
+}
+
+/// Splits the vectors into `chunks` pieces and sums their partial products on
+/// at most as many threads as there are cores, each taking every n-th piece.
+pub fn dot_product_parallel<T>(a: Vector<T>, b: Vector<T>, chunks: usize) -> Result<T>
+where
+    T: Debug
+        + Copy
+        + Default
+        + Add<Output = T>
+        + AddAssign
+        + Mul<Output = T>
+        + Send
+        + Sync
+        + 'static,
+{
+    if a.len() != b.len() {
+        return Err(ConcurrencyError::LengthMismatch {
+            expected: a.len(),
+            got: b.len(),
+        });
+    }
+    if a.is_empty() {
+        return Err(ConcurrencyError::EmptyInput);
+    }
+    if chunks == 0 {
+        return Err(ConcurrencyError::InvalidArgument(
+            "chunks must be greater than 0".to_string(),
+        ));
+    }
+
+    let chunk_size = a.len().div_ceil(chunks).max(1);
+    let pieces = a
+        .data
+        .chunks(chunk_size)
+        .zip(b.data.chunks(chunk_size))
+        .collect::<Vec<_>>();
+    // never more threads than pieces (at most min(chunks, len)) or cores
+    let num_threads = pieces.len().min(default_num_threads());
+    let partials = thread::scope(|s| {
+        let handles = (0..num_threads)
+            .map(|worker| {
+                let pieces = &pieces;
+                s.spawn(move || {
+                    let mut sum = T::default();
+                    for (x, y) in pieces.iter().skip(worker).step_by(num_threads) {
+                        for (&x, &y) in x.iter().zip(*y) {
+                            sum += x * y;
+                        }
+                    }
+                    sum
+                })
+            })
+            .collect::<Vec<_>>();
+
+        join_all(handles)
+    })?;
+
+    let mut sum = T::default();
+    for partial in partials {
+        sum += partial;
+    }
+    Ok(sum)
+}
+
+/// Like [`dot_product`], but widens each element into `Acc` before multiplying
+/// so neither the products nor the sum overflow `T`.
+pub fn dot_product_acc<T, Acc>(a: &Vector<T>, b: &Vector<T>) -> Result<Acc>
+where
+    T: Copy,
+    Acc: Default + AddAssign + Mul<Output = Acc> + From<T>,
+{
+    if a.len() != b.len() {
+        return Err(ConcurrencyError::LengthMismatch {
+            expected: a.len(),
+            got: b.len(),
+        });
+    }
+    if a.is_empty() {
+        return Err(ConcurrencyError::EmptyInput);
+    }
+
+    let mut sum = Acc::default();
+    for (&x, &y) in a.iter().zip(b.iter()) {
+        sum += Acc::from(x) * Acc::from(y);
+    }
+    Ok(sum)
+}
+
+/// `sum(a[i] * b[i] * weights[i])`; with all weights 1 this is the plain
+/// [`dot_product`].
+pub fn weighted_dot_product<T>(a: &Vector<T>, b: &Vector<T>, weights: &Vector<T>) -> Result<T>
+where
+    T: Copy + Default + AddAssign + Mul<Output = T>,
+{
+    for v in [b, weights] {
+        if v.len() != a.len() {
+            return Err(ConcurrencyError::LengthMismatch {
+                expected: a.len(),
+                got: v.len(),
+            });
+        }
+    }
+    if a.is_empty() {
+        return Err(ConcurrencyError::EmptyInput);
+    }
+
+    let mut sum = T::default();
+    for i in 0..a.len() {
+        sum += a[i] * b[i] * weights[i];
+    }
+    Ok(sum)
+}
+
+/// The `a.len() x b.len()` matrix with `(i, j) = a[i] * b[j]`.
+pub fn outer_product<T>(a: &Vector<T>, b: &Vector<T>) -> Matrix<T>
+where
+    T: Debug + Copy + Mul<Output = T>,
+{
+    let data = a
+        .iter()
+        .flat_map(|&x| b.iter().map(move |&y| x * y))
+        .collect::<Vec<_>>();
+    Matrix::new(data, a.len(), b.len())
+}
+
+pub fn cosine_similarity<T>(a: &Vector<T>, b: &Vector<T>) -> Result<T>
+where
+    T: Float + Debug + Default + AddAssign + Send + 'static,
+{
+    let dot = dot_product(Vector::new(a.as_slice()), Vector::new(b.as_slice()))?;
+    let norms = a.norm() * b.norm();
+    if norms.is_zero() {
+        return Err(ConcurrencyError::ZeroVector);
+    }
+    Ok(dot / norms)
+}
+
+#[must_use = "cross_product does nothing besides computing its result"]
+pub fn cross_product<T>(a: Vector<T>, b: Vector<T>) -> Result<Vector<T>>
+where
+    T: Debug + Copy + Mul<Output = T> + Sub<Output = T>,
+{
+    for v in [&a, &b] {
+        if v.len() != 3 {
+            return Err(ConcurrencyError::LengthMismatch {
+                expected: 3,
+                got: v.len(),
+            });
+        }
+    }
+
+    Ok(Vector::new(vec![
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::Bomb;
+    use anyhow::Result;
+
+    #[test]
+    fn test_norm_and_normalize() -> Result<()> {
+        let v = Vector::new(vec![3.0, 4.0]);
+        assert_eq!(v.norm(), 5.0);
+
+        let n = v.normalize()?;
+        assert!((n[0] - 0.6_f64).abs() < 1e-12);
+        assert!((n[1] - 0.8_f64).abs() < 1e-12);
+
+        assert_eq!(
+            Vector::new(vec![0.0, 0.0]).normalize().err(),
+            Some(ConcurrencyError::ZeroVector)
+        );
+        assert!(Vector::<f64>::new(vec![]).normalize().is_err());
+        for v in [
+            vec![f64::NAN, 1.0],
+            vec![f64::INFINITY, 1.0],
+            vec![1e300, 1e300],
+        ] {
+            assert!(matches!(
+                Vector::new(v).normalize(),
+                Err(ConcurrencyError::InvalidArgument(_))
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_dot_product_rejects_empty() {
+        assert!(dot_product(Vector::<i32>::new(vec![]), Vector::new(vec![])).is_err());
+        assert!(dot_product_parallel(Vector::<i32>::new(vec![]), Vector::new(vec![]), 2).is_err());
+    }
+
+    #[test]
+    fn test_dot_product_parallel() -> Result<()> {
+        let a = (0..1000).collect::<Vector<i64>>();
+        let b = (0..1000).map(|x| x % 7).collect::<Vector<i64>>();
+        let expected = dot_product(Vector::new(a.as_slice()), Vector::new(b.as_slice()))?;
+
+        // 1000 is not evenly divisible by 3 or 7
+        for chunks in [1, 3, 4, 7, 2000] {
+            let ret =
+                dot_product_parallel(Vector::new(a.as_slice()), Vector::new(b.as_slice()), chunks)?;
+            assert_eq!(ret, expected);
+        }
+
+        assert!(dot_product_parallel(a, b, 0).is_err());
+
+        // every chunk panics, not just the first one joined
+        let bombs = Vector::new(vec![Bomb(13); 64]);
+        assert!(matches!(
+            dot_product_parallel(bombs.clone(), bombs, 8),
+            Err(ConcurrencyError::Worker(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_cross_product() -> Result<()> {
+        let x = Vector::new(vec![1, 0, 0]);
+        let y = Vector::new(vec![0, 1, 0]);
+        assert_eq!(*cross_product(x, y)?, vec![0, 0, 1]);
+
+        let a = Vector::new(vec![1, 2]);
+        let b = Vector::new(vec![3, 4, 5]);
+        assert!(cross_product(a, b).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_collect_and_iterate() {
+        let v = (0..5).collect::<Vector<i32>>();
+        assert_eq!(*v, vec![0, 1, 2, 3, 4]);
+
+        let mut sum = 0;
+        for x in &v {
+            sum += x;
+        }
+        assert_eq!(sum, 10);
+
+        let doubled = v.into_iter().map(|x| x * 2).collect::<Vec<_>>();
+        assert_eq!(doubled, vec![0, 2, 4, 6, 8]);
+    }
+
+    #[test]
+    fn test_scale_add_sub() -> Result<()> {
+        let v = Vector::new(vec![1, 2, 3]);
+        let scaled = v.scale(2);
+        assert_eq!(*scaled, vec![2, 4, 6]);
+
+        let ones = Vector::new(vec![1, 1, 1]);
+        assert_eq!(*scaled.add(&ones)?, vec![3, 5, 7]);
+        assert_eq!(*v.add(&ones)?, vec![2, 3, 4]);
+        assert_eq!(*v.sub(&ones)?, vec![0, 1, 2]);
+
+        assert!(v.add(&Vector::new(vec![1, 1])).is_err());
+        assert!(v.sub(&Vector::new(vec![1, 1])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cosine_similarity() -> Result<()> {
+        let x = Vector::new(vec![1.0, 0.0]);
+        let y = Vector::new(vec![0.0, 2.0]);
+        assert_eq!(cosine_similarity(&x, &y)?, 0.0);
+
+        let v = Vector::new(vec![1.0, 2.0, 3.0]);
+        assert!((cosine_similarity(&v, &v)? - 1.0_f64).abs() < 1e-12);
+
+        let zero = Vector::new(vec![0.0, 0.0, 0.0]);
+        assert_eq!(
+            cosine_similarity(&v, &zero),
+            Err(ConcurrencyError::ZeroVector)
+        );
+        assert!(cosine_similarity(&v, &x).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_debug() {
+        let v = Vector::new(vec![1, 2, 3]);
+        assert_eq!(format!("{:?}", v), "Vector([1, 2, 3])");
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn test_simd_dot_product_matches_scalar() -> Result<()> {
+        let a = (0..4096)
+            .map(|x| (x as f64 * 0.37).sin())
+            .collect::<Vec<_>>();
+        let b = (0..4096)
+            .map(|x| (x as f64 * 0.11).cos())
+            .collect::<Vec<_>>();
+        let scalar = a.iter().zip(&b).map(|(x, y)| x * y).sum::<f64>();
+        let ret = dot_product(Vector::new(a.as_slice()), Vector::new(b.as_slice()))?;
+        assert!((ret - scalar).abs() < 1e-9);
+
+        // lengths that are not a multiple of the lane count use the scalar tail
+        let a = (0..4099).map(|x| x as f32 / 4099.0).collect::<Vec<_>>();
+        let scalar = a.iter().map(|x| x * x).sum::<f32>();
+        let ret = dot_product(Vector::new(a.as_slice()), Vector::new(a.as_slice()))?;
+        assert!((ret - scalar).abs() < 1e-2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_len_is_empty_iter() {
+        let v = Vector::new(vec![1, 2, 3]);
+        assert_eq!(v.len(), 3);
+        assert!(!v.is_empty());
+        assert_eq!(v.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert!(Vector::<i32>::new(vec![]).is_empty());
+    }
+
+    #[test]
+    fn test_dot_product_checked() -> Result<()> {
+        let a = Vector::new([1, 2, 3]);
+        let b = Vector::new([4, 5, 6]);
+        assert_eq!(dot_product_checked(&a, &b)?, 32);
+
+        let big = Vector::new([i32::MAX, 1]);
+        assert_eq!(
+            dot_product_checked(&big, &Vector::new([2, 1])),
+            Err(ConcurrencyError::Overflow)
+        );
+        assert_eq!(
+            dot_product_checked(&big, &Vector::new([1, 1])),
+            Err(ConcurrencyError::Overflow)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_vector_from_conversions() -> Result<()> {
+        let a: Vector<i32> = vec![1, 2, 3].into();
+        let b: Vector<i32> = [4, 5, 6].as_slice().into();
+        assert_eq!(*a, vec![1, 2, 3]);
+        assert_eq!(*b, vec![4, 5, 6]);
+        assert_eq!(dot_product(a, b)?, 32);
+        Ok(())
+    }
+
+    #[test]
+    fn test_weighted_dot_product() -> Result<()> {
+        let a = Vector::new([1, 2, 3]);
+        let b = Vector::new([4, 5, 6]);
+        let ones = Vector::new([1, 1, 1]);
+        assert_eq!(
+            weighted_dot_product(&a, &b, &ones)?,
+            dot_product(Vector::new(a.as_slice()), Vector::new(b.as_slice()))?
+        );
+        assert_eq!(weighted_dot_product(&a, &b, &Vector::new([2, 0, 1]))?, 26);
+        assert_eq!(
+            weighted_dot_product(&a, &b, &Vector::new([1, 1])),
+            Err(ConcurrencyError::LengthMismatch {
+                expected: 3,
+                got: 2
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_dot_slice_matches_dot_product() -> Result<()> {
+        let a = [1, 2, 3, 4];
+        let b = [5, 6, 7, 8];
+        assert_eq!(
+            dot_slice(&a, &b)?,
+            dot_product(Vector::new(a), Vector::new(b))?
+        );
+        assert!(dot_slice(&a, &b[..3]).is_err());
+        assert!(dot_slice::<i32>(&[], &[]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_dot_product_saturating() -> Result<()> {
+        let a = Vector::new([1i16, 2, 3]);
+        assert_eq!(dot_product_saturating(&a, &a)?, 14);
+
+        let big = Vector::new([200i16, 200]);
+        assert_eq!(dot_product_saturating(&big, &big)?, i16::MAX);
+        let neg = Vector::new([-200i16, -200]);
+        assert_eq!(dot_product_saturating(&big, &neg)?, i16::MIN);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sum_mean_min_max() -> Result<()> {
+        let v = Vector::new([3, 1, 4, 2]);
+        assert_eq!(v.sum(), 10);
+        assert_eq!(v.mean()?, 2.5);
+        assert_eq!(v.min(), Some(&1));
+        assert_eq!(v.max(), Some(&4));
+
+        let empty = Vector::<i32>::new([]);
+        assert_eq!(empty.sum(), 0);
+        assert_eq!(empty.mean(), Err(ConcurrencyError::EmptyInput));
+        assert_eq!(empty.min(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dot_product_acc() -> Result<()> {
+        // 100 * 100 already overflows an i8
+        let a = Vector::new([100i8, -100, 127]);
+        let b = Vector::new([100i8, 100, 127]);
+        assert_eq!(dot_product_acc::<i8, i64>(&a, &b)?, 16129);
+        assert!(dot_product_acc::<i8, i64>(&a, &Vector::new([1i8])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_chunks() {
+        let v = Vector::new([1, 2, 3, 4, 5]);
+        let chunks = v.chunks(2);
+        assert_eq!(
+            chunks.iter().map(|c| c.to_vec()).collect::<Vec<_>>(),
+            vec![vec![1, 2], vec![3, 4], vec![5]]
+        );
+        assert_eq!(v.chunks(5).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk size must be greater than 0")]
+    fn test_chunks_zero_size() {
+        Vector::new([1, 2]).chunks(0);
+    }
+
+    #[test]
+    fn test_has_non_finite() {
+        assert!(!Vector::new([1.0, 2.0]).has_non_finite());
+        assert!(Vector::new([1.0, f32::NAN]).has_non_finite());
+        assert!(Vector::new([f64::NEG_INFINITY, 2.0]).has_non_finite());
+    }
+
+    #[test]
+    fn test_clone() -> Result<()> {
+        let a = Vector::new([1, 2, 3]);
+        let b = Vector::new([4, 5, 6]);
+        assert_eq!(dot_product(a.clone(), b.clone())?, 32);
+        assert_eq!(*a, vec![1, 2, 3]);
+        assert_eq!(dot_product(a, b)?, 32);
+        Ok(())
+    }
+
+    #[test]
+    fn test_outer_product() {
+        let a = Vector::new([1, 2]);
+        let b = Vector::new([3, 4, 5]);
+        assert_eq!(
+            outer_product(&a, &b),
+            Matrix::new(vec![3, 4, 5, 6, 8, 10], 2, 3)
+        );
+    }
+}
@@ -0,0 +1,53 @@
+// lives in its own test binary so no other test spawns threads while the
+// process-wide thread count is being compared; the failing workers rely on
+// debug builds panicking on overflow
+#![cfg(all(target_os = "linux", debug_assertions))]
+
+use std::{fs, sync::Mutex, time::Duration};
+
+use anyhow::{anyhow, Result};
+use concurrency::{multiply_with_threads, multiply_with_timeout, Matrix};
+
+// the tests in here run in parallel, so each holds this while counting threads
+static COUNTING: Mutex<()> = Mutex::new(());
+
+fn thread_count() -> Result<usize> {
+    let status = fs::read_to_string("/proc/self/status")?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Threads:"))
+        .ok_or_else(|| anyhow!("no Threads line in /proc/self/status"))?
+        .trim()
+        .parse()
+        .map_err(Into::into)
+}
+
+#[test]
+fn test_multiply_joins_workers_on_error() -> Result<()> {
+    let a = Matrix::new(vec![1, i32::MAX, 3, 4], 2, 2);
+    let b = Matrix::new(vec![1, 2, i32::MAX, 4], 2, 2);
+    let wide = Matrix::new(vec![1; 3], 1, 3);
+
+    let _guard = COUNTING.lock().unwrap();
+    let before = thread_count()?;
+    for _ in 0..10 {
+        assert!(multiply_with_threads(&a, &b, 8).is_err());
+        assert!(multiply_with_threads(&a, &wide, 8).is_err());
+        assert_eq!(thread_count()?, before);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_multiply_with_timeout_joins_workers() -> Result<()> {
+    let a = Matrix::new((0..10_000).collect::<Vec<i64>>(), 100, 100);
+
+    let _guard = COUNTING.lock().unwrap();
+    let before = thread_count()?;
+    for _ in 0..10 {
+        // whether or not the deadline is hit, no worker may outlive the call
+        let _ = multiply_with_timeout(&a, &a, Duration::ZERO);
+        assert_eq!(thread_count()?, before);
+    }
+    Ok(())
+}